@@ -1,12 +1,12 @@
 #![feature(proc_macro_quote)]
 
-use proc_macro::TokenStream;
+use proc_macro::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
 use quote::quote;
 
 macro_rules! define_sniff_tool_annotation {
     ($name: ident) => {
         #[proc_macro_attribute]
-        pub fn $name(_attr: TokenStream, item: TokenStream) -> TokenStream {
+        pub fn $name(attr: TokenStream, item: TokenStream) -> TokenStream {
             let mut t = TokenStream::new();
 
             // If we're registering the sniff-test tool, add the actual attribute to check unsafe.
@@ -16,9 +16,15 @@ macro_rules! define_sniff_tool_annotation {
 
             let use_sniff_tool = rustflags || std::env::var("PLUGIN_ARGS").is_ok();
             if use_sniff_tool {
-                t.extend(TokenStream::from(quote!(
-                    #[sniff_tool::$name]
-                )));
+                // Pass along any arguments, as in `#[sniff_tool::$name(args)]`.
+                let mut path = TokenStream::from(quote!(sniff_tool::$name));
+                if !attr.is_empty() {
+                    path.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, attr))]);
+                }
+                t.extend([
+                    TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+                    TokenTree::Group(Group::new(Delimiter::Bracket, path)),
+                ]);
             }
 
             t.extend(item);
@@ -29,6 +35,10 @@ macro_rules! define_sniff_tool_annotation {
 
 define_sniff_tool_annotation!(check_unsafe);
 
+// Suppresses errors of the given kinds, e.g. `#[allow(unjustified_call, reason = "...")]`.
+// On statements and expressions, this needs `#![feature(proc_macro_hygiene, stmt_expr_attributes)]`.
+define_sniff_tool_annotation!(allow);
+
 // TODO: could be useful to have a macro for calling sniff_tool::[..]_pub in the future,
 // but for now, not worth the effort. Turns out we'd need the sniff tool attr to be after all
 // the prelude import stuff and with just a token tree its very hard to handle that.
@@ -57,7 +57,7 @@ impl DocStrSource {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AnnotationSource {
     DocComment(Vec<Span>),
     TomlOverride,
@@ -102,15 +102,11 @@ impl ExpressionAnnotation {
                     );
                     Ok(())
                 } else {
-                    let names = unconsidered
-                        .iter()
-                        .map(|a| &a.node.name)
-                        .collect::<Vec<&String>>();
                     Err(LocalError::CallMissedObligations {
                         func: in_fn.clone(),
                         callsite_comment: self.text.clone(),
                         callsite_span: from_span,
-                        obligations: names.into_iter().cloned().collect(),
+                        obligations: unconsidered,
                     })
                 }
             }
@@ -6,6 +6,7 @@ use rustc_span::source_map::{Spanned, respan};
 use crate::{
     ARGS,
     annotations::{AnnotationSource, Condition, DocStrSource, PropertyViolation},
+    codes::ErrorCode,
 };
 
 pub fn violation_from_text(
@@ -28,12 +29,13 @@ pub fn violation_from_text(
                 let msg = format!(
                     "couldn't determine conditional property violation for {def_id:?} from {source:?} based on text {text:?}",
                 );
-                match source {
+                let diag = match source {
                     AnnotationSource::DocComment(span) => {
-                        tcx.dcx().struct_span_err(span.clone(), msg).emit()
+                        tcx.dcx().struct_span_err(span.clone(), msg)
                     }
-                    AnnotationSource::TomlOverride => tcx.dcx().struct_err(msg).emit(),
+                    AnnotationSource::TomlOverride => tcx.dcx().struct_err(msg),
                 };
+                diag.with_note(ErrorCode::NoConditions.note()).emit();
                 panic!("see above error");
             }
             PropertyViolation::Unconditional
@@ -47,12 +49,13 @@ pub fn violation_from_text(
                 let msg = format!(
                     "malformed conditional property violation for {def_id:?} from {source:?}: {err_msg}",
                 );
-                match source {
+                let diag = match source {
                     AnnotationSource::DocComment(span) => {
-                        tcx.dcx().struct_span_err(span.clone(), msg).emit()
+                        tcx.dcx().struct_span_err(span.clone(), msg)
                     }
-                    AnnotationSource::TomlOverride => tcx.dcx().struct_err(msg).emit(),
+                    AnnotationSource::TomlOverride => tcx.dcx().struct_err(msg),
                 };
+                diag.with_note(ErrorCode::MalformedCondition.note()).emit();
                 panic!("see above error");
             } else {
                 PropertyViolation::Unconditional
@@ -8,9 +8,16 @@
 //! * 'requirement 2': Description of requirement 2
 //! """
 //! ```
+//!
+//! The `[sniff-test]` table is reserved for configuring sniff-test itself (see [`crate::config`]).
 
 use std::collections::HashMap;
 
+/// The file annotations (and configuration) are read from.
+pub const FILE_NAME: &str = "sniff-test.toml";
+/// The table holding configuration rather than a function's annotations.
+pub const CONFIG_TABLE: &str = "sniff-test";
+
 /// Struct encapsulating annotations parsed from a TOML file.
 #[derive(Default)]
 pub struct TomlAnnotation {
@@ -64,6 +71,9 @@ impl TomlAnnotation {
         // Parse each function's requirements
         let mut function_to_requirements_string: HashMap<String, String> = HashMap::new();
         for (function_name, value) in table {
+            if function_name == CONFIG_TABLE {
+                continue;
+            }
             let Some(inner_table) = value.as_table() else {
                 return Err(TomlParseError::Schema(format!(
                     "Expected a TOML table for function {function_name}"
@@ -1,6 +1,6 @@
 #![feature(rustc_private)]
 
-fn main() {
+fn main() -> std::process::ExitCode {
     sniff_test::env_logger_init(false);
-    rustc_plugin::cli_main(sniff_test::PrintAllItemsPlugin);
+    sniff_test::cli_main()
 }
@@ -0,0 +1,213 @@
+//! Acknowledging individual errors in the source with
+//! `#[sniff_tool::allow(unjustified_call, reason = "...")]` on a function, block or statement.
+
+use clap::ValueEnum;
+use rustc_ast::MetaItemInner;
+use rustc_hir::{
+    Expr, HirId, LetStmt, Stmt,
+    def_id::LocalDefId,
+    intravisit::{self, Visitor},
+};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{Span, Symbol, sym};
+
+use crate::{
+    check::{CheckedFn, FindingKind, LocalError},
+    properties::Property,
+};
+
+/// An error that was suppressed by an `allow` attribute.
+pub struct Suppressed<'tcx, P: Property> {
+    /// Only the parts of the original error that were suppressed.
+    pub error: LocalError<'tcx, P>,
+    pub reason: String,
+    /// The `allow` attribute itself.
+    pub attr_span: Span,
+}
+
+/// An `allow` attribute found in the source.
+#[derive(Debug)]
+struct Allow {
+    kinds: Vec<FindingKind>,
+    reason: String,
+    attr_span: Span,
+    /// The code it applies to.
+    covers: Span,
+    /// The function whose body it's in (or on).
+    owner: LocalDefId,
+}
+
+/// Take the errors suppressed by an `allow` attribute out of `errors`, warning about any
+/// attributes in the reachable `functions` that don't suppress anything.
+pub fn suppress<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    functions: &[CheckedFn],
+    errors: Vec<LocalError<'tcx, P>>,
+) -> (Vec<LocalError<'tcx, P>>, Vec<Suppressed<'tcx, P>>) {
+    let allows = find_allows(tcx);
+    if allows.is_empty() {
+        return (errors, Vec::new());
+    }
+
+    let mut used = vec![false; allows.len()];
+    let mut matching = |kind: FindingKind, span: Span| {
+        // Point at the code in this crate, even if it's within a macro.
+        let span = span.source_callsite();
+        let (i, _) = allows
+            .iter()
+            .enumerate()
+            .filter(|(_, allow)| allow.kinds.contains(&kind) && allow.covers.contains(span))
+            // Use the innermost attribute, like lint levels do.
+            .min_by_key(|(_, allow)| allow.covers.hi() - allow.covers.lo())?;
+        used[i] = true;
+        Some(i)
+    };
+
+    let mut remaining = Vec::new();
+    let mut suppressed = Vec::new();
+    for error in errors {
+        for (allow, error) in error.split_by(tcx, &mut matching) {
+            match allow {
+                Some(i) => suppressed.push(Suppressed {
+                    error,
+                    reason: allows[i].reason.clone(),
+                    attr_span: allows[i].attr_span,
+                }),
+                None => remaining.push(error),
+            }
+        }
+    }
+
+    for (allow, used) in allows.iter().zip(used) {
+        let checked = functions
+            .iter()
+            .any(|checked| checked.func.reach == allow.owner);
+        if checked && !used {
+            tcx.dcx()
+                .struct_span_warn(
+                    allow.attr_span,
+                    "this `sniff_tool::allow` attribute doesn't suppress any errors",
+                )
+                .with_help("remove it if the errors it was for have been fixed")
+                .emit();
+        }
+    }
+
+    (remaining, suppressed)
+}
+
+/// Find every `allow` attribute in the crate, emitting errors for any that are malformed.
+fn find_allows(tcx: TyCtxt) -> Vec<Allow> {
+    let mut finder = AllowFinder {
+        tcx,
+        owner: None,
+        allows: Vec::new(),
+    };
+    for owner in tcx.hir_body_owners() {
+        finder.owner = Some(owner);
+        let hir_id = tcx.local_def_id_to_hir_id(owner);
+        // Closures are expressions, so we'll find attributes on them in their parent's body.
+        if !tcx.is_closure_like(owner.to_def_id()) {
+            finder.check(hir_id, tcx.hir_span_with_body(hir_id));
+        }
+        finder.visit_body(tcx.hir_body_owned_by(owner));
+    }
+    finder.allows
+}
+
+struct AllowFinder<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    owner: Option<LocalDefId>,
+    allows: Vec<Allow>,
+}
+
+impl AllowFinder<'_> {
+    fn check(&mut self, hir_id: HirId, covers: Span) {
+        let path = [Symbol::intern("sniff_tool"), Symbol::intern("allow")];
+        for attr in self.tcx.hir_attrs(hir_id) {
+            if !attr.path_matches(&path) {
+                continue;
+            }
+            let attr_span = attr.span();
+            // Statements and their expressions can share attributes.
+            if self.allows.iter().any(|allow| allow.attr_span == attr_span) {
+                continue;
+            }
+            let args = attr.meta_item_list().unwrap_or_default();
+            if let Some((kinds, reason)) = self.parse(attr_span, &args) {
+                self.allows.push(Allow {
+                    kinds,
+                    reason,
+                    attr_span,
+                    covers,
+                    owner: self.owner.expect("only looking in bodies"),
+                });
+            }
+        }
+    }
+
+    fn parse(&self, attr_span: Span, args: &[MetaItemInner]) -> Option<(Vec<FindingKind>, String)> {
+        let dcx = self.tcx.dcx();
+        let mut kinds = Vec::new();
+        let mut reason = None;
+        for arg in args {
+            if arg.has_name(sym::reason) {
+                reason = arg.value_str().map(|reason| reason.to_string());
+            } else if let Some(ident) = arg.ident()
+                && arg.is_word()
+            {
+                // Attribute args have to be identifiers, so accept snake case versions of the names.
+                let Ok(kind) = FindingKind::from_str(&ident.as_str().replace('_', "-"), true)
+                else {
+                    dcx.span_err(arg.span(), format!("unknown kind of error `{ident}`"));
+                    return None;
+                };
+                kinds.push(kind);
+            } else {
+                dcx.span_err(arg.span(), "expected a kind of error or `reason = \"...\"`");
+                return None;
+            }
+        }
+
+        if kinds.is_empty() {
+            dcx.span_err(
+                attr_span,
+                "`sniff_tool::allow` needs the kinds of error to allow",
+            );
+            return None;
+        }
+        match reason {
+            Some(reason) if !reason.trim().is_empty() => Some((kinds, reason)),
+            _ => {
+                dcx.span_err(
+                    attr_span,
+                    "`sniff_tool::allow` needs a `reason = \"...\"` for allowing the errors",
+                );
+                None
+            }
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for AllowFinder<'tcx> {
+    type MaybeTyCtxt = TyCtxt<'tcx>;
+
+    fn maybe_tcx(&mut self) -> Self::MaybeTyCtxt {
+        self.tcx
+    }
+
+    fn visit_stmt(&mut self, stmt: &'tcx Stmt<'tcx>) -> Self::Result {
+        self.check(stmt.hir_id, stmt.span);
+        intravisit::walk_stmt(self, stmt);
+    }
+
+    fn visit_local(&mut self, local: &'tcx LetStmt<'tcx>) -> Self::Result {
+        self.check(local.hir_id, local.span);
+        intravisit::walk_local(self, local);
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx Expr<'tcx>) -> Self::Result {
+        self.check(ex.hir_id, ex.span);
+        intravisit::walk_expr(self, ex);
+    }
+}
@@ -0,0 +1,316 @@
+//! Baselines of known errors, so sniff-test can be adopted incrementally: only errors that
+//! aren't already recorded in the baseline fail the check.
+//!
+//! Errors are identified by the function they're in, their kind and what they're about (e.g.
+//! the callee), rather than by spans, so that unrelated edits don't invalidate the baseline.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{Read, Seek, Write},
+    path::Path,
+};
+
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check::{FindingKind, LocalError},
+    properties::{Axiom, Property},
+    reachability::CallKind,
+    report::report_file_stem,
+};
+
+/// The current version of the baseline format.
+const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Baseline {
+    version: u32,
+    /// The findings in each crate, by report file stem (e.g. `my_crate-lib`).
+    crates: BTreeMap<String, Vec<Finding>>,
+}
+
+impl Default for Baseline {
+    fn default() -> Self {
+        Baseline {
+            version: VERSION,
+            crates: BTreeMap::new(),
+        }
+    }
+}
+
+/// A single issue within an error, identified independently of where exactly it is.
+///
+/// The same finding can be recorded more than once, e.g. for two unjustified calls to the same
+/// function from the same caller.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct Finding {
+    function: String,
+    kind: FindingKind,
+    /// What the finding is about, like the axiom, callee or trait.
+    detail: String,
+}
+
+/// Record all of the crate's `errors` in the baseline at `path`, replacing whatever was
+/// recorded for it before.
+pub fn write<P: Property>(tcx: TyCtxt, path: &Path, errors: &[LocalError<'_, P>]) {
+    let mut findings = errors
+        .iter()
+        .flat_map(|error| findings(tcx, error))
+        .collect::<Vec<_>>();
+    findings.sort();
+    let count = findings.len();
+
+    let stem = report_file_stem(tcx);
+    let res = update(path, |baseline| {
+        if findings.is_empty() {
+            baseline.crates.remove(&stem);
+        } else {
+            baseline.crates.insert(stem, findings);
+        }
+    });
+    match res {
+        Ok(()) => println!(
+            "recorded {count} finding(s) in the {} crate to the baseline at {}",
+            tcx.crate_name(LOCAL_CRATE),
+            path.display()
+        ),
+        Err(e) => {
+            tcx.dcx().err(format!(
+                "couldn't write baseline to {}: {e:#}",
+                path.display()
+            ));
+        }
+    }
+}
+
+/// Remove everything recorded in the baseline at `path` from `errors`, noting any recorded
+/// findings that no longer occur.
+pub fn apply<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    path: &Path,
+    errors: Vec<LocalError<'tcx, P>>,
+) -> Vec<LocalError<'tcx, P>> {
+    let baseline = match read(path) {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            tcx.dcx().err(format!(
+                "couldn't read baseline from {}: {e:#}",
+                path.display()
+            ));
+            return errors;
+        }
+    };
+    let mut known = HashMap::<Finding, usize>::new();
+    for finding in baseline
+        .crates
+        .get(&report_file_stem(tcx))
+        .into_iter()
+        .flatten()
+    {
+        *known.entry(finding.clone()).or_default() += 1;
+    }
+    let recorded = known.values().sum::<usize>();
+
+    let mut is_known = |finding: Finding| match known.get_mut(&finding) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    };
+    let errors = errors
+        .into_iter()
+        .filter_map(|error| without_known(tcx, error, &mut is_known))
+        .collect::<Vec<_>>();
+
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let mut fixed = known
+        .into_iter()
+        .flat_map(|(finding, count)| std::iter::repeat_n(finding, count))
+        .collect::<Vec<_>>();
+    fixed.sort();
+    if recorded > fixed.len() {
+        println!(
+            "ignoring {} finding(s) in the {crate_name} crate recorded in the baseline",
+            recorded - fixed.len()
+        );
+    }
+    if !fixed.is_empty() {
+        println!(
+            "{} finding(s) recorded in the baseline no longer occur in the {crate_name} crate, \
+             so it can be tightened with `--write-baseline`:",
+            fixed.len()
+        );
+        for finding in fixed {
+            let kind = serde_json::to_value(finding.kind).expect("kinds serialize to strings");
+            println!(
+                "  {} {} in {}: {}",
+                finding.kind.code(),
+                kind.as_str().unwrap_or_default(),
+                finding.function,
+                finding.detail
+            );
+        }
+    }
+
+    errors
+}
+
+/// The `error` with any issues `is_known` accepts taken out, or `None` if that's all of them.
+fn without_known<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    error: LocalError<'tcx, P>,
+    is_known: &mut impl FnMut(Finding) -> bool,
+) -> Option<LocalError<'tcx, P>> {
+    let function = tcx.def_path_str(error.func().reach.to_def_id());
+    if let LocalError::Basic {
+        tcx,
+        func,
+        _property: property,
+        mut unjustified_axioms,
+        mut unjustified_calls,
+    } = error
+    {
+        unjustified_axioms.retain(|axiom| !is_known(axiom_finding(&function, &axiom.axiom)));
+        for calls in &mut unjustified_calls {
+            let finding = call_finding(tcx, &function, calls.kind, calls.call_to);
+            calls.from_spans.retain(|_| !is_known(finding.clone()));
+        }
+        unjustified_calls.retain(|calls| !calls.from_spans.is_empty());
+
+        (!unjustified_axioms.is_empty() || !unjustified_calls.is_empty()).then_some(
+            LocalError::Basic {
+                tcx,
+                func,
+                _property: property,
+                unjustified_axioms,
+                unjustified_calls,
+            },
+        )
+    } else {
+        let [finding] = findings(tcx, &error)
+            .try_into()
+            .expect("other errors are a single finding");
+        (!is_known(finding)).then_some(error)
+    }
+}
+
+/// Every finding in an `error`.
+fn findings<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> Vec<Finding> {
+    let function = tcx.def_path_str(error.func().reach.to_def_id());
+    let single = |kind: FindingKind, detail| {
+        vec![Finding {
+            function: function.clone(),
+            kind,
+            detail,
+        }]
+    };
+    match error {
+        LocalError::Basic {
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => {
+            let axioms = unjustified_axioms
+                .iter()
+                .map(|axiom| axiom_finding(&function, &axiom.axiom));
+            let calls = unjustified_calls.iter().flat_map(|calls| {
+                let finding = call_finding(tcx, &function, calls.kind, calls.call_to);
+                std::iter::repeat_n(finding, calls.from_spans.len())
+            });
+            axioms.chain(calls).collect()
+        }
+        LocalError::CallMissedObligations { obligations, .. } => {
+            let names = obligations
+                .iter()
+                .map(|condition| condition.node.name.as_str())
+                .collect::<Vec<_>>();
+            single(FindingKind::MissedObligations, names.join(", "))
+        }
+        LocalError::FnDefShouldHaveKeyword { needed_keyword, .. } => {
+            single(FindingKind::MissingKeyword, (*needed_keyword).to_owned())
+        }
+        LocalError::Trait {
+            inconsistent_w_trait,
+            ..
+        } => single(
+            FindingKind::TraitInconsistency,
+            tcx.def_path_str(*inconsistent_w_trait),
+        ),
+    }
+}
+
+fn axiom_finding(function: &str, axiom: &impl Axiom) -> Finding {
+    Finding {
+        function: function.to_owned(),
+        kind: FindingKind::UnjustifiedAxiom,
+        // Use the variant's name, which is less likely to change than how it's described.
+        detail: format!("{axiom:?}"),
+    }
+}
+
+fn call_finding(
+    tcx: TyCtxt,
+    function: &str,
+    kind: CallKind,
+    call_to: rustc_hir::def_id::DefId,
+) -> Finding {
+    let kind = FindingKind::for_call(kind);
+    Finding {
+        function: function.to_owned(),
+        kind,
+        detail: tcx.def_path_str(call_to),
+    }
+}
+
+fn read(path: &Path) -> anyhow::Result<Baseline> {
+    let mut file = File::open(path)?;
+    // Other crates may be recording their findings at the same time.
+    file.lock_shared()?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    parse(&contents)
+}
+
+/// Update the baseline at `path` (creating it if need be) while holding a lock on it, as other
+/// crates may be recording their findings at the same time.
+fn update(path: &Path, f: impl FnOnce(&mut Baseline)) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.lock()?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let mut baseline = if contents.trim().is_empty() {
+        Baseline::default()
+    } else {
+        parse(&contents)?
+    };
+    f(&mut baseline);
+
+    file.set_len(0)?;
+    file.rewind()?;
+    serde_json::to_writer_pretty(&mut file, &baseline)?;
+    writeln!(file)?;
+    Ok(())
+}
+
+fn parse(contents: &str) -> anyhow::Result<Baseline> {
+    let baseline = serde_json::from_str::<Baseline>(contents)?;
+    anyhow::ensure!(
+        baseline.version == VERSION,
+        "unsupported baseline version {} (expected {VERSION})",
+        baseline.version
+    );
+    Ok(baseline)
+}
@@ -0,0 +1,246 @@
+//! Only failing on errors in code that's changed since a git revision, e.g. on pull requests.
+//!
+//! Reachability is still computed from every entry point, so the errors we keep are exactly the
+//! ones a full check would find in the changed code. An error is kept if the function it's in
+//! changed, or if it's about a call whose callee's annotation changed.
+
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Context;
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{FileName, Span};
+
+use crate::{
+    annotations::{AnnotationSource, toml::FILE_NAME},
+    check::{CheckResults, LocalError},
+    properties::Property,
+};
+
+/// The lines changed in each file, by canonical path.
+#[derive(Debug, Default)]
+struct Changes {
+    lines: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+}
+
+impl Changes {
+    /// The changes in the working tree since `rev`, including untracked files.
+    fn since(rev: &str) -> anyhow::Result<Self> {
+        let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim());
+        let diff = git(&[
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            rev,
+            "--",
+        ])?;
+
+        let mut changes = Changes::default();
+        let mut file = None;
+        for line in diff.lines() {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                // Deleted files have no lines left to check.
+                file = path.strip_prefix("b/").map(|path| root.join(path));
+            } else if let Some(hunk) = line.strip_prefix("@@ ")
+                && let Some(file) = &file
+            {
+                let lines =
+                    parse_hunk(hunk).with_context(|| format!("unexpected hunk header `{line}`"))?;
+                changes.add(file, lines);
+            }
+        }
+
+        let untracked = git(&["ls-files", "--others", "--exclude-standard", "--full-name"])?;
+        for path in untracked.lines() {
+            changes.add(&root.join(path), 1..=usize::MAX);
+        }
+        Ok(changes)
+    }
+
+    fn add(&mut self, file: &Path, lines: RangeInclusive<usize>) {
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_owned());
+        self.lines.entry(file).or_default().push(lines);
+    }
+
+    fn touches_file(&self, file: &Path) -> bool {
+        file.canonicalize()
+            .is_ok_and(|file| self.lines.contains_key(&file))
+    }
+
+    fn touches(&self, tcx: TyCtxt, span: Span) -> bool {
+        let span = span.source_callsite();
+        if span.is_dummy() {
+            return false;
+        }
+        let source_map = tcx.sess.source_map();
+        let lo = source_map.lookup_char_pos(span.lo());
+        let hi = source_map.lookup_char_pos(span.hi());
+        let FileName::Real(name) = &lo.file.name else {
+            return false;
+        };
+        let Some(file) = name
+            .local_path()
+            .and_then(|path| std::path::absolute(path).ok())
+            .and_then(|path| path.canonicalize().ok())
+        else {
+            return false;
+        };
+        self.lines.get(&file).is_some_and(|changed| {
+            changed
+                .iter()
+                .any(|lines| *lines.start() <= hi.line && lo.line <= *lines.end())
+        })
+    }
+
+    /// Whether the definition of a local item changed, including its attributes (and so any
+    /// annotation in its doc comment).
+    fn touches_def(&self, tcx: TyCtxt, def: DefId) -> bool {
+        let Some(local) = def.as_local() else {
+            return false;
+        };
+        let hir_id = tcx.local_def_id_to_hir_id(local);
+        self.touches(tcx, tcx.hir_span_with_body(hir_id))
+            || tcx
+                .hir_attrs(hir_id)
+                .iter()
+                .any(|attr| self.touches(tcx, attr.span()))
+    }
+
+    /// Whether an annotation from `source` changed.
+    fn touches_annotation(&self, tcx: TyCtxt, source: &AnnotationSource) -> bool {
+        match source {
+            AnnotationSource::DocComment(spans) => {
+                spans.iter().any(|span| self.touches(tcx, *span))
+            }
+            AnnotationSource::TomlOverride => self.touches_file(Path::new(FILE_NAME)),
+        }
+    }
+}
+
+/// The new lines of a hunk from its header (e.g. `-12,3 +14,5 @@`). Pure deletions count as
+/// changing the line before them.
+fn parse_hunk(hunk: &str) -> Option<RangeInclusive<usize>> {
+    let new = hunk.split_whitespace().nth(1)?.strip_prefix('+')?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new.parse().ok()?, 1),
+    };
+    Some(start.max(1)..=(start + count.max(1) - 1).max(1))
+}
+
+/// Check that `rev` names a commit, before we try to find the changes since it in every crate.
+pub fn check_revision(rev: &str) -> anyhow::Result<()> {
+    git(&[
+        "rev-parse",
+        "--verify",
+        "--end-of-options",
+        &format!("{rev}^{{commit}}"),
+    ])?;
+    Ok(())
+}
+
+fn git(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git").args(args).output()?;
+    anyhow::ensure!(
+        output.status.success(),
+        "`git {}` failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Drop the errors and warnings in `results` that aren't in code changed since `rev`.
+pub fn retain_changed<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    rev: &str,
+    results: &mut CheckResults<'tcx, P>,
+) {
+    let changes = match Changes::since(rev) {
+        Ok(changes) => changes,
+        Err(e) => {
+            tcx.dcx()
+                .err(format!("couldn't find the changes since `{rev}`: {e:#}"));
+            return;
+        }
+    };
+
+    let before = issue_count(&results.errors) + issue_count(&results.warnings);
+    for errors in [&mut results.errors, &mut results.warnings] {
+        *errors = std::mem::take(errors)
+            .into_iter()
+            .filter_map(|error| changed_part(tcx, &changes, error))
+            .collect();
+    }
+    let ignored = before - issue_count(&results.errors) - issue_count(&results.warnings);
+    if ignored > 0 {
+        println!(
+            "ignoring {ignored} finding(s) in the {} crate outside the changes since `{rev}`",
+            tcx.crate_name(LOCAL_CRATE)
+        );
+    }
+}
+
+fn issue_count<P: Property>(errors: &[LocalError<'_, P>]) -> usize {
+    errors.iter().map(LocalError::issue_count).sum()
+}
+
+/// The part of `error` affected by the `changes`, or `None` if it isn't at all.
+fn changed_part<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    changes: &Changes,
+    error: LocalError<'tcx, P>,
+) -> Option<LocalError<'tcx, P>> {
+    if changes.touches_def(tcx, error.func().reach.to_def_id()) {
+        return Some(error);
+    }
+
+    match error {
+        // The function didn't change, so only calls whose callee's annotation did are new.
+        LocalError::Basic {
+            tcx,
+            func,
+            _property: property,
+            unjustified_axioms: _,
+            mut unjustified_calls,
+        } => {
+            unjustified_calls
+                .retain(|calls| changes.touches_annotation(tcx, &calls.annotation_source));
+            (!unjustified_calls.is_empty()).then_some(LocalError::Basic {
+                tcx,
+                func,
+                _property: property,
+                unjustified_axioms: Vec::new(),
+                unjustified_calls,
+            })
+        }
+        LocalError::CallMissedObligations {
+            ref obligations, ..
+        } => {
+            let conditions_changed = obligations.iter().any(|condition| {
+                if condition.span.is_dummy() {
+                    // The conditions came from a TOML override.
+                    changes.touches_file(Path::new(FILE_NAME))
+                } else {
+                    changes.touches(tcx, condition.span)
+                }
+            });
+            conditions_changed.then_some(error)
+        }
+        LocalError::Trait {
+            inconsistent_w_trait,
+            ..
+        } => changes
+            .touches_def(tcx, inconsistent_w_trait)
+            .then_some(error),
+        LocalError::FnDefShouldHaveKeyword { .. } => None,
+    }
+}
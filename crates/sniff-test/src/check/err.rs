@@ -1,103 +1,215 @@
-use crate::{check::LocalError, properties::FoundAxiom};
+use crate::{
+    annotations::{AnnotationSource, toml::FILE_NAME},
+    check::{LocalError, suggest},
+    properties::FoundAxiom,
+};
 use itertools::Itertools;
-use rustc_errors::Diag;
+use rustc_errors::{Applicability, Diag};
 use rustc_middle::ty::TyCtxt;
-use rustc_span::ErrorGuaranteed;
+use rustc_span::Span;
 
 use crate::{
     properties::Property,
-    reachability::{CallsWObligations, LocallyReachable},
+    reachability::{CallKind, CallsWObligations, LocallyReachable},
 };
 
-pub fn report_errors<'tcx, P: Property>(
-    tcx: TyCtxt<'tcx>,
-    _property: P,
-    errors: Vec<LocalError<'tcx, P>>,
-) -> ErrorGuaranteed {
-    errors
-        .into_iter()
-        .map(|error| report_error(tcx, error))
-        .last()
-        .expect("don't call this on empty errors")
-}
-
-fn report_error<'tcx, P: Property>(
+/// Add the details of an `error` to the diagnostic reporting it.
+pub fn decorate<'tcx, P: Property>(
     tcx: TyCtxt<'tcx>,
+    property: P,
     error: LocalError<'tcx, P>,
-) -> ErrorGuaranteed {
-    let dcx = tcx.dcx();
-    let def_span = tcx.def_span(error.func().reach);
+    diag: &mut Diag<'_, ()>,
+) {
     let fn_name = tcx.def_path_str(error.func().reach.to_def_id());
+    let code = error.kind().code();
 
     match error {
-        LocalError::Basic { tcx, func, _property, unjustified_axioms, unjustified_calls } => {
-            let mut diag = dcx.struct_span_err(
-                def_span,
-                summary::summary_string::<P>(&fn_name, &unjustified_axioms, &unjustified_calls),
+        LocalError::Basic {
+            tcx,
+            func,
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => {
+            diag.note(reachability_str(&fn_name, tcx, &func));
+            let justify = suggest::justify_uses(
+                tcx,
+                property,
+                func.reach,
+                &unjustified_axioms,
+                &unjustified_calls,
+            )
+            .suggestions;
+            let annotate = suggest::annotate_fn(tcx, property, func.reach, &unjustified_calls).map(
+                |mut annotate| {
+                    if !justify.is_empty() {
+                        annotate.message = format!("or {}", annotate.message);
+                    }
+                    annotate
+                },
             );
-
-            diag = diag.with_note(reachability_str(&fn_name, tcx, &func));
+            let suggestions = justify.into_iter().chain(annotate);
 
             for axiom in unjustified_axioms {
-                diag = extend_diag_axiom::<P>(diag, axiom);
+                extend_diag_axiom::<P>(diag, axiom);
             }
 
             for calls in unjustified_calls {
-                diag = extend_diag_calls(diag, tcx, calls);
+                extend_diag_calls(diag, tcx, property, calls);
+            }
+
+            // These all have `TODO`s for the user to fill in, so can't be applied blindly.
+            for suggestion in suggestions {
+                diag.multipart_suggestion_verbose(
+                    suggestion.message,
+                    suggestion.parts,
+                    Applicability::HasPlaceholders,
+                );
             }
+        }
+        LocalError::CallMissedObligations { .. } | LocalError::FnDefShouldHaveKeyword { .. } => {}
+        LocalError::Trait {
+            inconsistent_w_trait,
+            ..
+        } => {
+            diag.span_note(tcx.def_span(inconsistent_w_trait), "which is defined here");
+        }
+    }
+    diag.note(code.note());
+}
+
+/// Where the main diagnostic for an error should point.
+pub fn primary_span<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> Span {
+    match error {
+        LocalError::CallMissedObligations { callsite_span, .. } => *callsite_span,
+        LocalError::Basic { .. }
+        | LocalError::FnDefShouldHaveKeyword { .. }
+        | LocalError::Trait { .. } => tcx.def_span(error.func().reach),
+    }
+}
+
+/// The main message describing an error.
+pub fn message<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> String {
+    let fn_name = tcx.def_path_str(error.func().reach.to_def_id());
 
-            diag.emit()
-        },
-        LocalError::CallMissedObligations { callsite_comment: _, callsite_span, obligations, .. } => {
-            dcx.struct_span_err(
-                callsite_span,
-                format!("call to {fn_name} here fails to consider its named obligations {obligations:?}"),
-            ).emit()
-        },
+    match error {
+        LocalError::Basic {
+            func,
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => summary::summary_string::<P>(
+            fn_descr(tcx, func),
+            &fn_name,
+            unjustified_axioms,
+            unjustified_calls,
+        ),
+        LocalError::CallMissedObligations { obligations, .. } => {
+            let obligations = obligations
+                .iter()
+                .map(|condition| &condition.node.name)
+                .collect::<Vec<_>>();
+            format!(
+                "call to {fn_name} here fails to consider its named obligations {obligations:?}"
+            )
+        }
         LocalError::FnDefShouldHaveKeyword { needed_keyword, .. } => {
-            dcx.struct_span_err(
-                def_span,
-                format!("function definition of {fn_name} here should have the {needed_keyword} keyword because of the {} property", P::property_name()),
-            ).emit()
-        },
-        LocalError::Trait { inconsistent_w_trait, .. } => {
-            dcx.struct_span_err(
-                def_span,
-                format!("implementation {fn_name} here has {} obligations that are inconsistent with those on the definition of the {} trait", tcx.def_path_debug_str(inconsistent_w_trait), P::property_name()),
-            ).with_span_note(tcx.def_span(inconsistent_w_trait), "which is defined here").emit()
+            format!(
+                "function definition of {fn_name} here should have the {needed_keyword} keyword because of the {} property",
+                P::property_name()
+            )
+        }
+        LocalError::Trait {
+            inconsistent_w_trait,
+            ..
+        } => {
+            format!(
+                "implementation {fn_name} here has {} obligations that are inconsistent with those on the definition of the {} trait",
+                tcx.def_path_debug_str(*inconsistent_w_trait),
+                P::property_name()
+            )
         }
     }
 }
 
-fn extend_diag_axiom<'tcx, P: Property>(
-    diag: Diag<'tcx>,
-    axiom: FoundAxiom<'tcx, P::Axiom>,
-) -> Diag<'tcx> {
+fn extend_diag_axiom<P: Property>(diag: &mut Diag<'_, ()>, axiom: FoundAxiom<'_, P::Axiom>) {
     // TODO: add notes about the known requirements
-    diag.with_span_note(axiom.span, format!("{} here", axiom.axiom))
+    diag.span_note(axiom.span, format!("{} here", axiom.axiom));
 }
 
-fn extend_diag_calls<'tcx>(
-    diag: Diag<'tcx>,
-    tcx: TyCtxt<'tcx>,
+fn extend_diag_calls<P: Property>(
+    diag: &mut Diag<'_, ()>,
+    tcx: TyCtxt,
+    property: P,
     calls: CallsWObligations,
-) -> Diag<'tcx> {
+) {
     let call_to = tcx.def_path_str(calls.call_to);
-    diag.with_span_note(calls.from_spans, format!("{call_to} is called here"))
+    let msg = match calls.kind {
+        CallKind::Call => format!("{call_to} is called here"),
+        CallKind::Drop => format!("{call_to} is run by a value dropped here"),
+    };
+    diag.span_note(calls.from_spans, msg);
+
+    // Show what the justification needs to consider, so it can be written without having to
+    // look up the callee.
+    let conditions = if calls.conditions.is_empty() {
+        String::new()
+    } else {
+        let lines = calls
+            .conditions
+            .iter()
+            .map(|condition| {
+                let description = condition.node.description.trim();
+                if description.is_empty() {
+                    format!("- {}", condition.node.name)
+                } else {
+                    format!("- {}: {description}", condition.node.name)
+                }
+            })
+            .join("\n");
+        format!(" with the conditions:\n{lines}")
+    };
+    match calls.annotation_source {
+        AnnotationSource::DocComment(spans) => {
+            diag.span_note(
+                spans,
+                format!(
+                    "{call_to} has a `{}` section here{conditions}",
+                    property.fn_def_section()
+                ),
+            );
+        }
+        AnnotationSource::TomlOverride => {
+            diag.note(format!("{call_to} is annotated in {FILE_NAME}{conditions}"));
+        }
+    }
+}
+
+/// How to describe the kind of item we're reporting on (e.g. `function` or `closure`).
+pub fn fn_descr(tcx: TyCtxt, func: &LocallyReachable) -> &'static str {
+    let def_id = func.reach.to_def_id();
+    if tcx.is_closure_like(def_id) {
+        tcx.def_descr(def_id)
+    } else {
+        "function"
+    }
 }
 
 fn reachability_str(fn_name: &str, tcx: TyCtxt, reachable: &LocallyReachable) -> String {
     let reachability_str = reachable
         .through
         .iter()
-        .map(|def| {
-            let name = tcx.def_path_str(def.0);
+        .map(|(def, span, kind)| {
+            let name = tcx.def_path_str(*def);
             let s = tcx
                 .sess
                 .source_map()
-                .span_to_string(def.1, rustc_span::FileNameDisplayPreference::Local);
+                .span_to_string(*span, rustc_span::FileNameDisplayPreference::Local);
             let colon = s.find(": ").expect("should have a colon");
-            format!("{name} ({})", &s[..colon])
+            match kind {
+                CallKind::Call => format!("{name} ({})", &s[..colon]),
+                CallKind::Drop => format!("{name} ({}, dropped here)", &s[..colon]),
+            }
         })
         .chain(std::iter::once(format!("*{fn_name}*")))
         .join(" -> ");
@@ -109,35 +221,49 @@ mod summary {
     use itertools::Itertools;
 
     use crate::properties::{FoundAxiom, Property};
-    use crate::reachability::CallsWObligations;
+    use crate::reachability::{CallKind, CallsWObligations};
 
     pub fn summary_string<P: Property>(
+        fn_descr: &str,
         fn_name: &str,
         axioms: &[FoundAxiom<'_, P::Axiom>],
         calls: &[CallsWObligations],
     ) -> String {
         let axiom_summary = axiom_summary::<P>(axioms);
-        let call_summary = call_summary::<P>(calls);
-        let issue_summary = [axiom_summary, call_summary]
+        let calls_summary = call_summary::<P>(calls, CallKind::Call);
+        let drops_summary = call_summary::<P>(calls, CallKind::Drop);
+        let issue_summary = [axiom_summary, calls_summary, drops_summary]
             .into_iter()
             .flatten()
             .join(" and ");
 
         let kind = P::property_name();
-        format!("function {fn_name} directly contains {issue_summary}, but is not annotated {kind}")
+        format!(
+            "{fn_descr} {fn_name} directly contains {issue_summary}, but is not annotated {kind}"
+        )
     }
 
-    fn call_summary<P: Property>(calls: &[CallsWObligations]) -> Option<String> {
-        let count: usize = calls.iter().map(|call| call.from_spans.len()).sum();
+    fn call_summary<P: Property>(
+        calls: &[CallsWObligations],
+        call_kind: CallKind,
+    ) -> Option<String> {
+        let count: usize = calls
+            .iter()
+            .filter(|call| call.kind == call_kind)
+            .map(|call| call.from_spans.len())
+            .sum();
         let kind = P::property_name();
         let s = match count {
             1 => "",
             x if x > 1 => "s",
             _ => return None,
         };
-        Some(format!(
-            "{count} unjustified call{s} to annotated {kind} functions"
-        ))
+        Some(match call_kind {
+            CallKind::Call => format!("{count} unjustified call{s} to annotated {kind} functions"),
+            CallKind::Drop => {
+                format!("{count} unjustified drop{s} of values with annotated {kind} destructors")
+            }
+        })
     }
 
     fn axiom_summary<P: Property>(axioms: &[FoundAxiom<'_, P::Axiom>]) -> Option<String> {
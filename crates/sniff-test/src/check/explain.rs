@@ -0,0 +1,306 @@
+//! Explaining how a function is reachable, by listing every path to it from the entry points
+//! (rather than just the shortest one we note on errors).
+//!
+//! This shows which entry points are responsible for a finding, and so whether narrowing them
+//! would make it go away.
+
+use std::collections::{HashMap, HashSet};
+
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::{
+    annotations::{PropertyViolation, parse_expr},
+    check::{CheckResults, expr},
+    properties::Property,
+    reachability::CallKind,
+    report::SourceLocation,
+};
+
+/// A call from one reachable function to another.
+#[derive(Debug, Clone, Copy)]
+struct Call {
+    to: usize,
+    kind: CallKind,
+    /// The first place the call is made.
+    span: Span,
+}
+
+/// A path through the call graph, as the entry point it starts from followed by each call made.
+type Path = (usize, Vec<Call>);
+
+/// Print up to `max_paths` distinct paths from the entry points to the function at `def_path`,
+/// along with the annotations on each function and call along the way.
+pub fn explain<P: Property>(
+    tcx: TyCtxt,
+    property: P,
+    results: &CheckResults<'_, P>,
+    def_path: &str,
+    max_paths: usize,
+) {
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let functions = &results.functions;
+    let Some(target) = functions
+        .iter()
+        .position(|checked| tcx.def_path_str(checked.func.reach.to_def_id()) == def_path)
+    else {
+        let exists = tcx
+            .hir_body_owners()
+            .any(|def| tcx.def_path_str(def.to_def_id()) == def_path);
+        if exists {
+            println!(
+                "`{def_path}` isn't reachable from any of the {} entry points in the {crate_name} crate",
+                results.entry_points.len()
+            );
+        } else {
+            println!("there's no function `{def_path}` in the {crate_name} crate");
+        }
+        return;
+    };
+
+    let calls = call_graph(results);
+    let (paths, cut_short) = find_paths(tcx, results, &calls, target, max_paths);
+
+    let location = SourceLocation::new(tcx, tcx.def_span(functions[target].func.reach));
+    let bounded = if cut_short {
+        " (stopping there, use `--max-paths` to see more)"
+    } else {
+        ""
+    };
+    println!(
+        "`{def_path}` ({}:{}) is reachable by {} path{} in the {crate_name} crate{bounded}:",
+        location.file,
+        location.start_line,
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" }
+    );
+
+    for (i, (entry, path)) in paths.iter().enumerate() {
+        let kind = results
+            .entry_points
+            .iter()
+            .find(|(def, _)| *def == functions[*entry].func.reach)
+            .map(|(_, kind)| kind)
+            .expect("paths start at an entry point");
+        let kind = serde_json::to_value(kind).expect("entry kinds serialize to strings");
+        println!(
+            "  path {} (entry point: {}):",
+            i + 1,
+            kind.as_str().unwrap_or_default().replace('_', " ")
+        );
+        print_fn(tcx, property, results, *entry);
+        let mut from = *entry;
+        for call in path {
+            print_call(tcx, property, results, from, call);
+            print_fn(tcx, property, results, call.to);
+            from = call.to;
+        }
+    }
+}
+
+/// The calls from each reachable function to other reachable functions, in source order.
+fn call_graph<P: Property>(results: &CheckResults<'_, P>) -> Vec<Vec<Call>> {
+    let index = results
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(i, checked)| (checked.func.reach.to_def_id(), i))
+        .collect::<HashMap<DefId, _>>();
+
+    results
+        .functions
+        .iter()
+        .map(|checked| {
+            let calls = checked
+                .func
+                .calls_to
+                .iter()
+                .map(|(callee, spans)| (CallKind::Call, callee, spans));
+            let drops = checked
+                .func
+                .drops
+                .iter()
+                .map(|(callee, spans)| (CallKind::Drop, callee, spans));
+            let mut calls = calls
+                .chain(drops)
+                .filter_map(|(kind, callee, spans)| {
+                    Some(Call {
+                        to: *index.get(callee)?,
+                        kind,
+                        span: *spans.first()?,
+                    })
+                })
+                .collect::<Vec<_>>();
+            calls.sort_by_key(|call| (call.span.lo(), call.kind == CallKind::Drop));
+            calls
+        })
+        .collect()
+}
+
+/// Find up to `max_paths` simple paths from any entry point to `target`, and whether there were more
+/// we left out.
+fn find_paths<P: Property>(
+    tcx: TyCtxt,
+    results: &CheckResults<'_, P>,
+    calls: &[Vec<Call>],
+    target: usize,
+    max_paths: usize,
+) -> (Vec<Path>, bool) {
+    // Only bother exploring functions the target can actually be reached from.
+    let mut reaches_target = HashSet::from([target]);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (from, calls) in calls.iter().enumerate() {
+            if !reaches_target.contains(&from)
+                && calls.iter().any(|call| reaches_target.contains(&call.to))
+            {
+                reaches_target.insert(from);
+                changed = true;
+            }
+        }
+    }
+
+    // Entry points aren't in any particular order, so go through them by name.
+    let mut entries = results
+        .entry_points
+        .iter()
+        .filter_map(|(entry, _)| {
+            results
+                .functions
+                .iter()
+                .position(|checked| checked.func.reach == *entry)
+        })
+        .filter(|entry| reaches_target.contains(entry))
+        .map(|entry| (tcx.def_path_str(results.functions[entry].func.reach), entry))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    // Look for one more path than we need, to know whether we're leaving any out.
+    let limit = max_paths.saturating_add(1);
+    let mut paths = Vec::new();
+    for (_, entry) in entries {
+        if paths.len() >= limit {
+            break;
+        }
+        let mut on_path = vec![entry];
+        let mut path = Vec::new();
+        extend_paths(
+            calls,
+            &reaches_target,
+            target,
+            limit - paths.len(),
+            &mut on_path,
+            &mut path,
+            &mut |path| paths.push((entry, path.to_vec())),
+        );
+    }
+    let cut_short = paths.len() > max_paths;
+    paths.truncate(max_paths);
+    (paths, cut_short)
+}
+
+/// Depth-first search for up to `max_paths` paths to `target` extending the current `path`,
+/// returning how many were found.
+fn extend_paths(
+    calls: &[Vec<Call>],
+    reaches_target: &HashSet<usize>,
+    target: usize,
+    max_paths: usize,
+    on_path: &mut Vec<usize>,
+    path: &mut Vec<Call>,
+    found: &mut dyn FnMut(&[Call]),
+) -> usize {
+    let current = *on_path.last().expect("paths start at an entry point");
+    if current == target {
+        found(path);
+        return 1;
+    }
+
+    let mut count = 0;
+    for call in &calls[current] {
+        if count >= max_paths {
+            break;
+        }
+        if !reaches_target.contains(&call.to) || on_path.contains(&call.to) {
+            continue;
+        }
+        on_path.push(call.to);
+        path.push(*call);
+        count += extend_paths(
+            calls,
+            reaches_target,
+            target,
+            max_paths - count,
+            on_path,
+            path,
+            found,
+        );
+        path.pop();
+        on_path.pop();
+    }
+    count
+}
+
+fn print_fn<P: Property>(tcx: TyCtxt, property: P, results: &CheckResults<'_, P>, func: usize) {
+    let checked = &results.functions[func];
+    let location = SourceLocation::new(tcx, tcx.def_span(checked.func.reach));
+    let annotation = match checked.annotation.as_ref().map(|annotation| {
+        (
+            annotation.creates_obligation().is_some(),
+            &annotation.local_violation_annotation,
+        )
+    }) {
+        Some((true, PropertyViolation::Conditionally(conditions))) if !conditions.is_empty() => {
+            let names = conditions
+                .iter()
+                .map(|condition| format!("`{}`", condition.node.name))
+                .collect::<Vec<_>>();
+            format!(
+                ", has a `{}` section with conditions {}",
+                property.fn_def_section(),
+                names.join(", ")
+            )
+        }
+        Some((true, _)) => format!(", has a `{}` section", property.fn_def_section()),
+        Some((false, _)) => format!(", annotated as never violating {}", P::property_name()),
+        None => String::new(),
+    };
+    println!(
+        "    {} ({}:{}{annotation})",
+        tcx.def_path_str(checked.func.reach.to_def_id()),
+        location.file,
+        location.start_line
+    );
+}
+
+fn print_call<P: Property>(
+    tcx: TyCtxt,
+    property: P,
+    results: &CheckResults<'_, P>,
+    from: usize,
+    call: &Call,
+) {
+    let calling_fn = results.functions[from].func.reach;
+    let callee = results.functions[call.to].func.reach.to_def_id();
+    let call_expr = match call.kind {
+        CallKind::Call => Some(expr::find_expr_for_call(tcx, callee, calling_fn, call.span)),
+        CallKind::Drop => expr::find_expr_containing(tcx, calling_fn, call.span),
+    };
+    let justification = call_expr
+        .and_then(|call_expr| parse_expr(tcx, call_expr, property))
+        .map_or_else(String::new, |annotation| {
+            let text = annotation.text.split_whitespace().collect::<Vec<_>>();
+            format!(", justified by \"{}\"", text.join(" "))
+        });
+    let location = SourceLocation::new(tcx, call.span.source_callsite());
+    let kind = match call.kind {
+        CallKind::Call => "call",
+        CallKind::Drop => "drop",
+    };
+    println!(
+        "      -> {kind} at {}:{}{justification}",
+        location.file, location.start_line
+    );
+}
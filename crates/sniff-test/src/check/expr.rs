@@ -1,5 +1,5 @@
 use rustc_hir::{
-    Expr,
+    Expr, Stmt, StmtKind,
     def_id::{DefId, LocalDefId},
     intravisit::{self, Visitor},
 };
@@ -39,3 +39,43 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpanExprFinder<'tcx> {
         intravisit::walk_expr(self, ex);
     }
 }
+
+/// Finds the innermost expression in `within`'s body that contains `span`, if any.
+pub fn find_expr_containing(tcx: TyCtxt<'_>, within: LocalDefId, span: Span) -> Option<&Expr<'_>> {
+    let body = tcx.hir_body_owned_by(within);
+    let mut f = ContainingExprFinder(tcx, span, None);
+    f.visit_body(body);
+    f.2
+}
+
+struct ContainingExprFinder<'tcx>(TyCtxt<'tcx>, Span, Option<&'tcx Expr<'tcx>>);
+
+impl<'tcx> intravisit::Visitor<'tcx> for ContainingExprFinder<'tcx> {
+    type MaybeTyCtxt = TyCtxt<'tcx>;
+
+    fn maybe_tcx(&mut self) -> Self::MaybeTyCtxt {
+        self.0
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx Expr<'tcx>) -> Self::Result {
+        if !ex.span.contains(self.1) {
+            return;
+        }
+
+        // We visit outer expressions first, so this will end up as the innermost one.
+        self.2 = Some(ex);
+        intravisit::walk_expr(self, ex);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'tcx Stmt<'tcx>) -> Self::Result {
+        // Temporaries in a `let` are dropped at the end of the statement, outside its initializer,
+        // but that's still the expression the statement (and any justification on it) is about.
+        if stmt.span.contains(self.1)
+            && let StmtKind::Let(local) = stmt.kind
+            && let Some(init) = local.init
+        {
+            self.2 = Some(init);
+        }
+        intravisit::walk_stmt(self, stmt);
+    }
+}
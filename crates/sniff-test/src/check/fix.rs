@@ -0,0 +1,156 @@
+//! Applying our suggestions directly to the source, so that a crate can adopt sniff-test without
+//! hand-writing a skeleton for every error.
+//!
+//! We only ever insert code, so existing formatting is kept, and everything we insert is a
+//! justification or annotation, so a fixed error won't come back on the next run.
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{Pos, SourceFile};
+
+use crate::{
+    check::{
+        LocalError,
+        suggest::{self, Suggestion},
+    },
+    properties::Property,
+    report::SourceLocation,
+};
+
+/// Code to insert at a byte offset into a file.
+type Insert = (usize, String);
+
+/// Fix as many `errors` as we can by editing the source files, returning those we couldn't fix.
+pub fn apply_fixes<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    property: P,
+    errors: Vec<LocalError<'tcx, P>>,
+) -> Vec<LocalError<'tcx, P>> {
+    let mut fixes = Vec::new();
+    let mut unfixed = Vec::new();
+    let mut added_keyword = false;
+    for error in errors {
+        match fixes_for(tcx, property, &error) {
+            Some(error_fixes) => {
+                added_keyword |= matches!(error, LocalError::FnDefShouldHaveKeyword { .. });
+                fixes.extend(error_fixes);
+            }
+            None => unfixed.push(error),
+        }
+    }
+
+    let sm = tcx.sess.source_map();
+    let mut by_file: BTreeMap<PathBuf, (Arc<SourceFile>, Vec<Insert>)> = BTreeMap::new();
+    for fix in &fixes {
+        for (span, code) in &fix.parts {
+            let pos = sm.lookup_byte_offset(span.lo());
+            let Some(path) = pos.sf.name.clone().into_local_path() else {
+                continue;
+            };
+            let (_, inserts) = by_file
+                .entry(path)
+                .or_insert_with(|| (pos.sf.clone(), Vec::new()));
+            inserts.push((pos.pos.to_usize(), code.clone()));
+        }
+
+        let location = SourceLocation::new(tcx, fix.parts[0].0);
+        println!(
+            "  {}:{}:{}: {}",
+            location.file, location.start_line, location.start_column, fix.message
+        );
+    }
+
+    let mut edited_files = 0;
+    for (path, (source_file, inserts)) in by_file {
+        match insert_into_file(&path, &source_file, inserts) {
+            Ok(()) => edited_files += 1,
+            Err(e) => {
+                tcx.dcx()
+                    .struct_warn(format!("couldn't apply fixes to {}: {e}", path.display()))
+                    .emit();
+            }
+        }
+    }
+
+    println!(
+        "applied {} fixes across {edited_files} file(s) in the {} crate, {} error(s) left to fix by hand",
+        fixes.len(),
+        tcx.crate_name(LOCAL_CRATE),
+        unfixed.len(),
+    );
+
+    // e.g. calls to a function that's now `unsafe` have to be in an `unsafe` block.
+    if added_keyword {
+        println!("note: callers of functions that had a keyword added may need to be updated");
+    }
+
+    unfixed
+}
+
+/// The edits that would fix an `error`, if we know how to fix all of it.
+fn fixes_for<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    property: P,
+    error: &LocalError<'tcx, P>,
+) -> Option<Vec<Suggestion>> {
+    match error {
+        LocalError::Basic {
+            func,
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => {
+            // If the function already has the keyword, it's meant to pass obligations on to its
+            // callers and is just missing the annotation to say what they are.
+            let forwards_obligations = property
+                .fn_def_keyword()
+                .is_some_and(|keyword| suggest::has_keyword(tcx, func.reach, keyword));
+            if forwards_obligations
+                && let Some(annotate) =
+                    suggest::annotate_fn(tcx, property, func.reach, unjustified_calls)
+            {
+                return Some(vec![annotate]);
+            }
+
+            let justify = suggest::justify_uses(
+                tcx,
+                property,
+                func.reach,
+                unjustified_axioms,
+                unjustified_calls,
+            );
+            justify.all_placed.then_some(justify.suggestions)
+        }
+        LocalError::FnDefShouldHaveKeyword {
+            fn_def,
+            needed_keyword,
+        } => suggest::add_keyword(tcx, fn_def.reach, needed_keyword).map(|fix| vec![fix]),
+        LocalError::Trait { .. } | LocalError::CallMissedObligations { .. } => None,
+    }
+}
+
+/// Insert code at the given byte offsets in a file, as long as it hasn't changed since we
+/// compiled it.
+fn insert_into_file(
+    path: &PathBuf,
+    source_file: &SourceFile,
+    mut inserts: Vec<Insert>,
+) -> std::io::Result<()> {
+    let mut contents = std::fs::read_to_string(path)?;
+    if source_file.src.as_deref() != Some(&contents) {
+        return Err(std::io::Error::other(
+            "it has changed since it was compiled",
+        ));
+    }
+
+    // Insert from the back so earlier offsets stay valid.
+    inserts.sort();
+    inserts.dedup();
+    for (offset, code) in inserts.into_iter().rev() {
+        contents.insert_str(offset, &code);
+    }
+
+    std::fs::write(path, contents)
+}
@@ -0,0 +1,231 @@
+//! Inferring the conditions that unannotated functions would have if they passed their
+//! obligations on to their callers, rather than justifying them.
+//!
+//! This lets reviewers see what annotating a function would mean for the rest of the crate
+//! before they decide whether to justify or forward its obligations.
+
+use std::collections::HashMap;
+
+use rustc_hir::def_id::{DefId, LOCAL_CRATE, LocalDefId};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::{
+    annotations::{Condition, PropertyViolation, parse_expr},
+    check::{CheckResults, LocalError, expr, suggest},
+    properties::{Axiom, Property},
+    reachability::{CallKind, CallsWObligations},
+    report::SourceLocation,
+};
+
+/// The conditions a function would need, and where they come from.
+#[derive(Debug, Default, Clone)]
+struct Inferred {
+    conditions: Vec<Condition>,
+    /// Uses whose requirements we don't know, so can't be turned into conditions.
+    unknown: Vec<String>,
+    /// What within the function needs the conditions.
+    sources: Vec<String>,
+}
+
+impl Inferred {
+    fn add_source(&mut self, source: String) {
+        if !self.sources.contains(&source) {
+            self.sources.push(source);
+        }
+    }
+
+    fn add_unknown(&mut self, unknown: String) {
+        if !self.unknown.contains(&unknown) {
+            self.unknown.push(unknown);
+        }
+    }
+
+    fn add_condition(&mut self, condition: &Condition) {
+        if !self.conditions.contains(condition) {
+            self.conditions.push(condition.clone());
+        }
+    }
+
+    /// Add everything from a callee, returning whether anything was new.
+    fn merge(&mut self, callee: &Inferred) -> bool {
+        let before = (self.conditions.len(), self.unknown.len());
+        for condition in &callee.conditions {
+            self.add_condition(condition);
+        }
+        for unknown in &callee.unknown {
+            self.add_unknown(unknown.clone());
+        }
+        before != (self.conditions.len(), self.unknown.len())
+    }
+}
+
+/// Print the `property` section each function that fails the check would need to pass its
+/// obligations on, including those it would inherit from the other functions it calls.
+pub fn print_inferred_conditions<P: Property>(
+    tcx: TyCtxt,
+    property: P,
+    results: &CheckResults<'_, P>,
+) {
+    let mut inferred = HashMap::new();
+    for error in &results.errors {
+        if let LocalError::Basic {
+            func,
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } = error
+        {
+            // A function's axioms and calls can be reported as separate errors.
+            let direct = inferred.entry(func.reach).or_default();
+            for axiom in unjustified_axioms {
+                add_axiom(direct, &axiom.axiom);
+            }
+            for calls in unjustified_calls {
+                add_calls(tcx, direct, calls);
+            }
+        }
+    }
+
+    // Any unannotated function that calls one which now has conditions would inherit them
+    // (unless it justifies the call), so keep passing them up until nothing changes.
+    let unannotated = results
+        .functions
+        .iter()
+        .filter(|checked| {
+            checked
+                .annotation
+                .as_ref()
+                .is_none_or(|annotation| annotation.creates_obligation().is_none())
+        })
+        .collect::<Vec<_>>();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for caller in &unannotated {
+            let callees = caller
+                .func
+                .calls_to
+                .iter()
+                .map(|(callee, spans)| (CallKind::Call, callee, spans))
+                .chain(
+                    caller
+                        .func
+                        .drops
+                        .iter()
+                        .map(|(callee, spans)| (CallKind::Drop, callee, spans)),
+                );
+            for (kind, callee, spans) in callees {
+                let Some(local_callee) = callee.as_local() else {
+                    continue;
+                };
+                let Some(from_callee) = inferred.get(&local_callee).cloned() else {
+                    continue;
+                };
+                if !suggest::can_forward_obligations(tcx, local_callee)
+                    || !has_unjustified_call(tcx, property, caller.func.reach, *callee, kind, spans)
+                {
+                    continue;
+                }
+
+                let caller_inferred = inferred.entry(caller.func.reach).or_default();
+                caller_inferred.add_source(call_source(tcx, kind, *callee));
+                changed |= caller_inferred.merge(&from_callee);
+            }
+        }
+    }
+
+    println!(
+        "inferred `{}` conditions for the {} crate:",
+        property.fn_def_section(),
+        tcx.crate_name(LOCAL_CRATE)
+    );
+    for checked in &results.functions {
+        let func = checked.func.reach;
+        if let Some(inferred) = inferred.get(&func) {
+            print_inferred(tcx, property, func, inferred);
+        }
+    }
+}
+
+/// Add the known requirements of an axiom.
+fn add_axiom<A: Axiom>(inferred: &mut Inferred, axiom: &A) {
+    let source = format!("the {axiom}");
+    match axiom.known_requirements() {
+        Some(PropertyViolation::Never) => return,
+        Some(PropertyViolation::Conditionally(conditions)) if !conditions.is_empty() => {
+            for condition in &conditions {
+                inferred.add_condition(&condition.node);
+            }
+        }
+        Some(PropertyViolation::Unconditional | PropertyViolation::Conditionally(_)) | None => {
+            inferred.add_unknown(source.clone());
+        }
+    }
+    inferred.add_source(source);
+}
+
+/// Add the conditions of a callee.
+fn add_calls(tcx: TyCtxt, inferred: &mut Inferred, calls: &CallsWObligations) {
+    let source = call_source(tcx, calls.kind, calls.call_to);
+    if calls.conditions.is_empty() {
+        inferred.add_unknown(source.clone());
+    }
+    for condition in &calls.conditions {
+        inferred.add_condition(&condition.node);
+    }
+    inferred.add_source(source);
+}
+
+fn call_source(tcx: TyCtxt, kind: CallKind, callee: DefId) -> String {
+    let callee = tcx.def_path_str(callee);
+    match kind {
+        CallKind::Call => format!("calls to `{callee}`"),
+        CallKind::Drop => format!("drops running `{callee}`"),
+    }
+}
+
+/// Whether any of the calls to `callee` from `in_fn` at `spans` isn't justified.
+fn has_unjustified_call<P: Property>(
+    tcx: TyCtxt,
+    property: P,
+    in_fn: LocalDefId,
+    callee: DefId,
+    kind: CallKind,
+    spans: &[Span],
+) -> bool {
+    spans.iter().any(|span| {
+        let call_expr = match kind {
+            CallKind::Call => Some(expr::find_expr_for_call(tcx, callee, in_fn, *span)),
+            CallKind::Drop => expr::find_expr_containing(tcx, in_fn, *span),
+        };
+        call_expr
+            .and_then(|call_expr| parse_expr(tcx, call_expr, property))
+            .is_none()
+    })
+}
+
+fn print_inferred<P: Property>(tcx: TyCtxt, property: P, func: LocalDefId, inferred: &Inferred) {
+    let location = SourceLocation::new(tcx, tcx.def_span(func));
+    println!(
+        "  {} ({}:{}), from {}:",
+        tcx.def_path_str(func.to_def_id()),
+        location.file,
+        location.start_line,
+        inferred.sources.join(", ")
+    );
+    println!("    /// # {}", property.fn_def_section());
+    for condition in &inferred.conditions {
+        println!(
+            "    /// * {}: {}",
+            condition.name,
+            condition.description.trim()
+        );
+    }
+    for unknown in &inferred.unknown {
+        println!("    /// * TODO: the requirements of {unknown}");
+    }
+    if !suggest::can_forward_obligations(tcx, func) {
+        println!("    (this function can't be annotated, so these must be justified within it)");
+    }
+}
@@ -0,0 +1,229 @@
+//! Reporting errors as `sniff_tool::*` tool lints, so their levels can be controlled with the
+//! standard `#[allow]`, `#[expect]`, `#[warn]` and `#[deny]` attributes and `-A`/`-W`/`-D` flags.
+
+use std::sync::OnceLock;
+
+use rustc_errors::{Diag, LintDiagnostic};
+use rustc_hir::HirId;
+use rustc_lint::LintStore;
+use rustc_middle::{lint::LevelAndSource, ty::TyCtxt, util::Providers};
+use rustc_session::{
+    Session, declare_tool_lint,
+    lint::{Level, Lint},
+};
+use rustc_span::Symbol;
+
+use crate::{
+    check::{FindingKind, LocalError, err, expr},
+    properties::Property,
+};
+
+declare_tool_lint! {
+    /// An axiom used without a justification.
+    pub sniff_tool::UNJUSTIFIED_AXIOM,
+    Deny,
+    "an axiom used without a justification",
+    report_in_external_macro: true
+}
+
+declare_tool_lint! {
+    /// A call to a function with obligations without a justification.
+    pub sniff_tool::UNJUSTIFIED_CALL,
+    Deny,
+    "a call to a function with obligations without a justification",
+    report_in_external_macro: true
+}
+
+declare_tool_lint! {
+    /// A value dropped without a justification, whose drop has obligations.
+    pub sniff_tool::UNJUSTIFIED_DROP,
+    Deny,
+    "a value dropped without a justification, whose drop has obligations",
+    report_in_external_macro: true
+}
+
+declare_tool_lint! {
+    /// A justification that doesn't consider all of the callee's named conditions.
+    pub sniff_tool::MISSED_OBLIGATIONS,
+    Deny,
+    "a justification that doesn't consider all of the callee's named conditions",
+    report_in_external_macro: true
+}
+
+declare_tool_lint! {
+    /// A function with obligations missing a keyword (e.g. `unsafe`).
+    pub sniff_tool::MISSING_KEYWORD,
+    Deny,
+    "a function with obligations missing a keyword (e.g. `unsafe`)",
+    report_in_external_macro: true
+}
+
+declare_tool_lint! {
+    /// A trait method implementation with obligations inconsistent with the trait's definition.
+    pub sniff_tool::TRAIT_INCONSISTENCY,
+    Deny,
+    "a trait method implementation with obligations inconsistent with the trait's definition",
+    report_in_external_macro: true
+}
+
+impl FindingKind {
+    pub fn lint(self) -> &'static Lint {
+        match self {
+            FindingKind::UnjustifiedAxiom => UNJUSTIFIED_AXIOM,
+            FindingKind::UnjustifiedCall => UNJUSTIFIED_CALL,
+            FindingKind::UnjustifiedDrop => UNJUSTIFIED_DROP,
+            FindingKind::MissedObligations => MISSED_OBLIGATIONS,
+            FindingKind::MissingKeyword => MISSING_KEYWORD,
+            FindingKind::TraitInconsistency => TRAIT_INCONSISTENCY,
+        }
+    }
+}
+
+/// Register our lints, so rustc knows about them when checking lint attributes and flags.
+pub fn register_lints(_sess: &Session, store: &mut LintStore) {
+    store.register_lints(&[
+        UNJUSTIFIED_AXIOM,
+        UNJUSTIFIED_CALL,
+        UNJUSTIFIED_DROP,
+        MISSED_OBLIGATIONS,
+        MISSING_KEYWORD,
+        TRAIT_INCONSISTENCY,
+    ]);
+}
+
+/// The errors at each lint level, after splitting them up by the level of each issue in them.
+pub struct Leveled<'tcx, P: Property> {
+    /// Errors at a `deny` or `forbid` level, which fail the check.
+    pub denied: Vec<LocalError<'tcx, P>>,
+    /// Errors at a `warn` level.
+    pub warned: Vec<LocalError<'tcx, P>>,
+    /// Errors at an `allow` or `expect` level, which still need emitting to fulfill any
+    /// `#[expect]`s.
+    pub allowed: Vec<LocalError<'tcx, P>>,
+}
+
+/// Sort `errors` by the lint level of each issue in them.
+pub fn by_level<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    errors: Vec<LocalError<'tcx, P>>,
+) -> Leveled<'tcx, P> {
+    let mut leveled = Leveled {
+        denied: Vec::new(),
+        warned: Vec::new(),
+        allowed: Vec::new(),
+    };
+    for error in errors {
+        let func = error.func().reach;
+        let parts = error.split_by(tcx, |kind, span| {
+            let hir_id = expr::find_expr_containing(tcx, func, span)
+                .map_or_else(|| tcx.local_def_id_to_hir_id(func), |expr| expr.hir_id);
+            let LevelAndSource { level, lint_id, .. } = tcx.lint_level_at_node(kind.lint(), hir_id);
+            // Each lint is for a single kind, and issues under different `#[expect]`s need
+            // emitting separately to fulfill both.
+            (kind, level, lint_id)
+        });
+        for ((_, level, _), error) in parts {
+            match level {
+                Level::Deny | Level::Forbid => leveled.denied.push(error),
+                Level::Warn | Level::ForceWarn => leveled.warned.push(error),
+                Level::Allow | Level::Expect => leveled.allowed.push(error),
+            }
+        }
+    }
+    leveled
+}
+
+/// Emit each of the `errors` as the lint for its kind.
+pub fn emit<'tcx, P: Property>(tcx: TyCtxt<'tcx>, property: P, errors: Vec<LocalError<'tcx, P>>) {
+    for error in errors {
+        let kind = error.kind();
+        tcx.emit_node_span_lint(
+            kind.lint(),
+            lint_node(tcx, &error),
+            err::primary_span(tcx, &error),
+            ErrorLint {
+                tcx,
+                property,
+                error,
+            },
+        );
+    }
+}
+
+/// Emit each of the `errors` as a warning rather than at the level of its lint, noting `why`, so
+/// they don't stop compilation and crates depending on this one can still be analysed.
+pub fn emit_as_warnings<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    property: P,
+    errors: Vec<LocalError<'tcx, P>>,
+    why: &str,
+) {
+    for error in errors {
+        let mut diag = tcx
+            .dcx()
+            .struct_span_warn(err::primary_span(tcx, &error), err::message(tcx, &error));
+        err::decorate(tcx, property, error, &mut diag);
+        diag.note(why.to_owned());
+        diag.emit();
+    }
+}
+
+/// The node whose lint level applies to an error: the innermost expression containing its
+/// first issue, or else the function it's in.
+fn lint_node<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> HirId {
+    let func = error.func().reach;
+    let span = match error {
+        LocalError::Basic {
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => unjustified_axioms
+            .first()
+            .map(|axiom| axiom.span)
+            .or_else(|| unjustified_calls.first()?.from_spans.first().copied()),
+        LocalError::CallMissedObligations { callsite_span, .. } => Some(*callsite_span),
+        LocalError::FnDefShouldHaveKeyword { .. } | LocalError::Trait { .. } => None,
+    };
+    span.and_then(|span| expr::find_expr_containing(tcx, func, span))
+        .map_or_else(|| tcx.local_def_id_to_hir_id(func), |expr| expr.hir_id)
+}
+
+struct ErrorLint<'tcx, P: Property> {
+    tcx: TyCtxt<'tcx>,
+    property: P,
+    error: LocalError<'tcx, P>,
+}
+
+impl<P: Property> LintDiagnostic<'_, ()> for ErrorLint<'_, P> {
+    fn decorate_lint(self, diag: &mut Diag<'_, ()>) {
+        diag.primary_message(err::message(self.tcx, &self.error));
+        err::decorate(self.tcx, self.property, self.error, diag);
+    }
+}
+
+/// The `check_expectations` query rustc would have run.
+static CHECK_EXPECTATIONS: OnceLock<fn(TyCtxt<'_>, Option<Symbol>)> = OnceLock::new();
+
+/// Put off checking for unfulfilled `#[expect]`s until [`check_expectations`] is called, as
+/// rustc would otherwise do it before we've had a chance to emit our lints.
+pub fn override_queries(_sess: &Session, providers: &mut Providers) {
+    let _ = CHECK_EXPECTATIONS.set(providers.queries.check_expectations);
+    providers.queries.check_expectations = |tcx, tool| {
+        // Tools check just their own lints, which don't include ours.
+        if tool.is_some() {
+            check_expectations_now(tcx, tool);
+        }
+    };
+}
+
+/// Warn about any `#[expect]`s that weren't fulfilled, now that all lints have been emitted.
+pub fn check_expectations(tcx: TyCtxt) {
+    check_expectations_now(tcx, None);
+}
+
+fn check_expectations_now(tcx: TyCtxt, tool: Option<Symbol>) {
+    let check = CHECK_EXPECTATIONS
+        .get()
+        .expect("queries are overridden before analysis");
+    check(tcx, tool);
+}
@@ -1,22 +1,119 @@
 use crate::{
-    annotations::{self, DefAnnotation, parse_expr, toml::TomlAnnotation},
+    annotations::{
+        self, Condition, DefAnnotation, ExpressionAnnotation, parse_expr, toml::TomlAnnotation,
+    },
+    check::allow::Suppressed,
     properties::{self, FoundAxiom, Property},
-    reachability::{self, CallsWObligations, LocallyReachable},
+    reachability::{self, CallKind, CallsWObligations, EntryKind, LocallyReachable},
 };
+use clap::ValueEnum;
 use rustc_hir::def_id::{DefId, LOCAL_CRATE, LocalDefId};
 use rustc_middle::ty::TyCtxt;
-use rustc_span::Span;
+use rustc_span::{Span, source_map::Spanned};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
+pub mod allow;
+pub mod baseline;
+pub mod changed;
 pub mod err;
+pub mod explain;
 mod expr;
+pub mod fix;
+pub mod infer;
+pub mod lints;
+pub mod severity;
+pub mod suggest;
 
 #[derive(Debug, Default, Clone)]
 pub struct CheckStats {
-    pub entrypoints: usize,
+    /// The number of entry points, broken down by why they were chosen.
+    pub entrypoints: BTreeMap<EntryKind, usize>,
     pub total_fns_checked: usize,
     pub w_obligation: usize,
     pub w_no_obligation: usize,
     pub calls_checked: usize,
+    /// The number of individual issues suppressed by `allow` attributes.
+    pub suppressed: usize,
+}
+
+/// Everything we found while checking a crate for a property.
+pub struct CheckResults<'tcx, P: Property> {
+    /// The functions analysis started from, and why they were chosen.
+    pub entry_points: Vec<(LocalDefId, EntryKind)>,
+    /// Every function reachable from the entry points.
+    pub functions: Vec<CheckedFn>,
+    /// The errors that fail the check.
+    pub errors: Vec<LocalError<'tcx, P>>,
+    /// The errors that are only warned about, according to their lint level.
+    pub warnings: Vec<LocalError<'tcx, P>>,
+    /// The errors whose lints are allowed or expected, which don't fail the check.
+    pub allowed: Vec<LocalError<'tcx, P>>,
+    /// The errors suppressed by `allow` attributes, which don't fail the check.
+    pub suppressed: Vec<Suppressed<'tcx, P>>,
+    pub stats: CheckStats,
+    /// The annotation overrides from `sniff-test.toml` that were used.
+    pub toml_annotations: TomlAnnotation,
+}
+
+impl<P: Property> CheckResults<'_, P> {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// A reachable function, along with the annotations that were used when checking it.
+pub struct CheckedFn {
+    pub func: LocallyReachable,
+    pub annotation: Option<DefAnnotation>,
+    /// The axioms and calls within this function that were justified at their use.
+    pub justifications: Vec<Justification>,
+}
+
+/// A justification comment on an axiom or a call to a function with obligations.
+#[derive(Debug)]
+pub struct Justification {
+    /// The axiom or call being justified.
+    pub span: Span,
+    pub text: String,
+}
+
+impl Justification {
+    fn new(span: Span, annotation: &ExpressionAnnotation) -> Self {
+        Justification {
+            span,
+            text: annotation.text.trim().to_owned(),
+        }
+    }
+}
+
+/// The kinds of individual issue an error can be made up of.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    /// An axiom used without a justification.
+    UnjustifiedAxiom,
+    /// A call to a function with obligations without a justification.
+    UnjustifiedCall,
+    /// A value dropped without a justification, whose drop has obligations.
+    UnjustifiedDrop,
+    /// A justification that doesn't consider all of the callee's named conditions.
+    MissedObligations,
+    /// A function with obligations missing a keyword (e.g. `unsafe`).
+    MissingKeyword,
+    /// A trait method implementation with obligations inconsistent with the trait's definition.
+    TraitInconsistency,
+}
+
+impl FindingKind {
+    pub fn for_call(kind: CallKind) -> Self {
+        match kind {
+            CallKind::Call => FindingKind::UnjustifiedCall,
+            CallKind::Drop => FindingKind::UnjustifiedDrop,
+        }
+    }
 }
 
 /// Checks that all local functions in the crate are properly annotated.
@@ -24,45 +121,21 @@ pub fn check_crate_for_property<P: Property>(
     tcx: TyCtxt<'_>,
     property: P,
     is_dependency: bool,
-) -> Result<CheckStats, Vec<LocalError<'_, P>>> {
-    // Parse TOML annotations from file
-    let toml_path = "sniff-test.toml";
-    let toml_annotations = match TomlAnnotation::from_file(toml_path) {
-        Ok(annotations) => annotations,
-        Err(e) => {
-            tcx.dcx()
-                .struct_warn(format!(
-                    "Failed to parse TOML annotations from {toml_path}: {e:?}"
-                ))
-                .emit();
-            TomlAnnotation::default()
-        }
-    };
+) -> CheckResults<'_, P> {
+    let toml_annotations = load_toml_annotations(tcx);
 
     let mut stats = CheckStats::default();
     let entry = reachability::analysis_entry_points::<P>(tcx, is_dependency);
 
-    // Debug print all our entries and where they are in the src
-    // (this isn't actually needed for analysis)
-    {
-        let entries = entry
-            .iter()
-            .map(|local| {
-                let span = tcx.optimized_mir(local.to_def_id()).span;
-                (local, span)
-            })
-            .collect::<Vec<_>>();
-        log::info!(
-            "the {} entry functions for {} in {} are {entries:#?}",
-            entry.len(),
-            P::property_name(),
-            tcx.crate_name(LOCAL_CRATE)
-        );
-    }
+    log_entry_points::<P>(tcx, &entry);
 
-    stats.entrypoints = entry.len();
-    let reachable = reachability::locally_reachable_from(tcx, entry);
+    for (_, kind) in &entry {
+        *stats.entrypoints.entry(*kind).or_default() += 1;
+    }
+    let reachable =
+        reachability::locally_reachable_from(tcx, entry.iter().map(|(local, _)| *local));
     let mut local_errors = Vec::new();
+    let mut functions = Vec::new();
 
     log::info!(
         "the {} reachable functions for {} in {} are {reachable:#?}",
@@ -76,24 +149,20 @@ pub fn check_crate_for_property<P: Property>(
 
     for func in reachable {
         stats.total_fns_checked += 1;
-        match annotations::parse_fn_def(tcx, &toml_annotations, func.reach, property) {
+        // Closures and coroutines can't be annotated, so they're covered by the annotation on the
+        // function they're defined in.
+        let annotated = tcx.typeck_root_def_id(func.reach.to_def_id());
+        let annotation = annotations::parse_fn_def(tcx, &toml_annotations, annotated, property);
+        match &annotation {
             Some(annotation) if annotation.creates_obligation().is_some() => {
                 stats.w_obligation += 1;
-                if let Some(trait_def) = is_impl_of_trait(tcx, func.reach)
-                    && let Err(e) = check_consistent_w_trait_requirements(
-                        tcx,
-                        &func,
-                        &annotation,
-                        trait_def,
-                        property,
-                        &toml_annotations,
-                    )
-                {
-                    local_errors.push(e);
-                }
-                if let Err(e) = property.additional_check(tcx, func.clone()) {
-                    local_errors.push(e);
-                }
+                local_errors.extend(check_annotated_fn_def(
+                    tcx,
+                    &func,
+                    annotation,
+                    property,
+                    &toml_annotations,
+                ));
                 // TODO: in the future, could check to make sure this annotation doesn't create unneeded obligations.
                 log::debug!(
                     "fn {:?} has obligations {:?}, we'll trust it...",
@@ -103,9 +172,14 @@ pub fn check_crate_for_property<P: Property>(
             }
             _ => {
                 stats.w_no_obligation += 1;
-                reachable_no_obligations.push(func);
+                reachable_no_obligations.push((functions.len(), func.clone()));
             }
         }
+        functions.push(CheckedFn {
+            func,
+            annotation,
+            justifications: Vec::new(),
+        });
     }
 
     log::info!(
@@ -115,15 +189,73 @@ pub fn check_crate_for_property<P: Property>(
         tcx.crate_name(LOCAL_CRATE)
     );
 
-    local_errors.extend(reachable_no_obligations.into_iter().filter_map(|func| {
-        check_function_for_property(tcx, &toml_annotations, func, property, &mut stats).err()
-    }));
+    local_errors.extend(
+        reachable_no_obligations
+            .into_iter()
+            .filter_map(|(i, func)| {
+                check_function_for_property(
+                    tcx,
+                    &toml_annotations,
+                    func,
+                    property,
+                    &mut stats,
+                    &mut functions[i].justifications,
+                )
+                .err()
+            }),
+    );
 
-    if !local_errors.is_empty() {
-        return Err(local_errors);
+    let (errors, suppressed) = allow::suppress(tcx, &functions, local_errors);
+    let leveled = lints::by_level(tcx, errors);
+    stats.suppressed = suppressed
+        .iter()
+        .map(|suppressed| suppressed.error.issue_count())
+        .sum();
+
+    CheckResults {
+        entry_points: entry,
+        functions,
+        errors: leveled.denied,
+        warnings: leveled.warned,
+        allowed: leveled.allowed,
+        suppressed,
+        stats,
+        toml_annotations,
     }
+}
 
-    Ok(stats)
+/// Debug print all our entries and where they are in the src
+/// (this isn't actually needed for analysis)
+fn log_entry_points<P: Property>(tcx: TyCtxt, entry: &[(LocalDefId, EntryKind)]) {
+    let entries = entry
+        .iter()
+        .map(|(local, kind)| {
+            let span = tcx.optimized_mir(local.to_def_id()).span;
+            (local, kind, span)
+        })
+        .collect::<Vec<_>>();
+    log::info!(
+        "the {} entry functions for {} in {} are {entries:#?}",
+        entry.len(),
+        P::property_name(),
+        tcx.crate_name(LOCAL_CRATE)
+    );
+}
+
+/// Parse TOML annotations from file, warning if we can't.
+fn load_toml_annotations(tcx: TyCtxt) -> TomlAnnotation {
+    let toml_path = annotations::toml::FILE_NAME;
+    match TomlAnnotation::from_file(toml_path) {
+        Ok(annotations) => annotations,
+        Err(e) => {
+            tcx.dcx()
+                .struct_warn(format!(
+                    "Failed to parse TOML annotations from {toml_path}: {e:?}"
+                ))
+                .emit();
+            TomlAnnotation::default()
+        }
+    }
 }
 
 pub enum LocalError<'tcx, P: Property> {
@@ -142,7 +274,7 @@ pub enum LocalError<'tcx, P: Property> {
         func: LocallyReachable,
         callsite_comment: String,
         callsite_span: Span,
-        obligations: Vec<String>,
+        obligations: Vec<Spanned<Condition>>,
     },
     FnDefShouldHaveKeyword {
         fn_def: LocallyReachable,
@@ -150,7 +282,7 @@ pub enum LocalError<'tcx, P: Property> {
     },
 }
 
-impl<P: Property> LocalError<'_, P> {
+impl<'tcx, P: Property> LocalError<'tcx, P> {
     pub fn func(&self) -> &LocallyReachable {
         match self {
             Self::Basic { func, .. }
@@ -162,6 +294,118 @@ impl<P: Property> LocalError<'_, P> {
             } => func,
         }
     }
+
+    /// How many individual axioms, calls or other issues make up this error.
+    pub fn issue_count(&self) -> usize {
+        match self {
+            Self::Basic {
+                unjustified_axioms,
+                unjustified_calls,
+                ..
+            } => {
+                unjustified_axioms.len()
+                    + unjustified_calls
+                        .iter()
+                        .map(|calls| calls.from_spans.len())
+                        .sum::<usize>()
+            }
+            _ => 1,
+        }
+    }
+
+    /// The kind of this error, or of its first issue if it's made up of several kinds.
+    pub fn kind(&self) -> FindingKind {
+        match self {
+            Self::Basic {
+                unjustified_axioms,
+                unjustified_calls,
+                ..
+            } => match (unjustified_axioms.first(), unjustified_calls.first()) {
+                (None, Some(calls)) => FindingKind::for_call(calls.kind),
+                _ => FindingKind::UnjustifiedAxiom,
+            },
+            Self::Trait { .. } => FindingKind::TraitInconsistency,
+            Self::CallMissedObligations { .. } => FindingKind::MissedObligations,
+            Self::FnDefShouldHaveKeyword { .. } => FindingKind::MissingKeyword,
+        }
+    }
+
+    /// Split this error into parts by the group `group_of` puts each issue in, given its kind
+    /// and where it is.
+    pub fn split_by<K: PartialEq + Copy>(
+        self,
+        tcx: TyCtxt<'tcx>,
+        mut group_of: impl FnMut(FindingKind, Span) -> K,
+    ) -> Vec<(K, Self)> {
+        let Self::Basic {
+            tcx,
+            func,
+            _property: property,
+            unjustified_axioms,
+            unjustified_calls,
+        } = self
+        else {
+            return vec![(group_of(self.kind(), err::primary_span(tcx, &self)), self)];
+        };
+
+        // The axioms and calls in each group, in the order we first see them.
+        let mut parts = Vec::<Part<'tcx, K, P::Axiom>>::new();
+        for axiom in unjustified_axioms {
+            let group = group_of(FindingKind::UnjustifiedAxiom, axiom.span);
+            Part::for_group(&mut parts, group).axioms.push(axiom);
+        }
+        for calls in unjustified_calls {
+            let kind = FindingKind::for_call(calls.kind);
+            for span in &calls.from_spans {
+                let group = group_of(kind, *span);
+                let part_calls = &mut Part::for_group(&mut parts, group).calls;
+                match part_calls.last_mut() {
+                    Some(last) if last.call_to == calls.call_to && last.kind == calls.kind => {
+                        last.from_spans.push(*span);
+                    }
+                    _ => part_calls.push(CallsWObligations {
+                        from_spans: vec![*span],
+                        ..calls.clone()
+                    }),
+                }
+            }
+        }
+
+        parts
+            .into_iter()
+            .map(|part| {
+                let error = Self::Basic {
+                    tcx,
+                    func: func.clone(),
+                    _property: property,
+                    unjustified_axioms: part.axioms,
+                    unjustified_calls: part.calls,
+                };
+                (part.group, error)
+            })
+            .collect()
+    }
+}
+
+/// The parts of a basic error in a particular group, see [`LocalError::split_by`].
+struct Part<'tcx, K, A: properties::Axiom> {
+    group: K,
+    axioms: Vec<FoundAxiom<'tcx, A>>,
+    calls: Vec<CallsWObligations>,
+}
+
+impl<K: PartialEq, A: properties::Axiom> Part<'_, K, A> {
+    fn for_group(parts: &mut Vec<Self>, group: K) -> &mut Self {
+        if let Some(i) = parts.iter().position(|part| part.group == group) {
+            return &mut parts[i];
+        }
+        parts.push(Part {
+            group,
+            axioms: Vec::new(),
+            calls: Vec::new(),
+        });
+        parts.last_mut().expect("just pushed it")
+    }
 }
 
 fn check_function_for_property<'tcx, P: Property>(
@@ -170,14 +414,19 @@ fn check_function_for_property<'tcx, P: Property>(
     func: LocallyReachable,
     property: P,
     stats: &mut CheckStats,
+    justifications: &mut Vec<Justification>,
 ) -> Result<(), LocalError<'tcx, P>> {
     // Look for all axioms within this function
     let axioms = properties::find_axioms(tcx, &func, property).collect::<Vec<_>>();
     log::debug!("fn {:?} has raw axioms {:#?}", func.reach, axioms);
-    let unjustified_axioms = axioms
-        .into_iter()
-        .filter(only_unjustified_axioms(tcx, property))
-        .collect::<Vec<_>>();
+    let mut unjustified_axioms = Vec::new();
+    for axiom in axioms {
+        log::debug!("getting seeing if axiom {axiom:?} has justification");
+        match parse_expr(tcx, axiom.found_in, property) {
+            Some(annotation) => justifications.push(Justification::new(axiom.span, &annotation)),
+            None => unjustified_axioms.push(axiom),
+        }
+    }
 
     // Find all calls that have obligations.
     let calls = reachability::find_calls_w_obligations(tcx, toml_annotations, &func, property)
@@ -192,7 +441,7 @@ fn check_function_for_property<'tcx, P: Property>(
     let mut unjustified_calls = Vec::new();
     let only_unjustified = only_unjustified_callsites(tcx, func.clone(), property);
     for c in calls {
-        match only_unjustified(c) {
+        match only_unjustified(c, justifications) {
             JustificationStatus::AllCallsJustified => (),
             JustificationStatus::ImproperJustification(err) => return Err(err),
             JustificationStatus::SomeNotJustified(remaining) => unjustified_calls.push(remaining),
@@ -227,6 +476,45 @@ fn check_function_for_property<'tcx, P: Property>(
     }
 }
 
+/// Check the definition of a function annotated with obligations is consistent with them.
+fn check_annotated_fn_def<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    func: &LocallyReachable,
+    annotation: &DefAnnotation,
+    property: P,
+    toml_annotations: &TomlAnnotation,
+) -> Vec<LocalError<'tcx, P>> {
+    // Closures and coroutines share their enclosing function's annotation, which is checked there.
+    if tcx.is_closure_like(func.reach.to_def_id()) {
+        return Vec::new();
+    }
+
+    let trait_def = is_impl_of_trait(tcx, func.reach);
+    // Destructors can't be `unsafe` or differ from `Drop::drop`, so their obligations are
+    // discharged wherever their values are dropped instead.
+    if trait_def.is_some() && trait_def == tcx.lang_items().drop_trait() {
+        return Vec::new();
+    }
+
+    let mut errors = Vec::new();
+    if let Some(trait_def) = trait_def
+        && let Err(e) = check_consistent_w_trait_requirements(
+            tcx,
+            func,
+            annotation,
+            trait_def,
+            property,
+            toml_annotations,
+        )
+    {
+        errors.push(e);
+    }
+    if let Err(e) = property.additional_check(tcx, func.clone()) {
+        errors.push(e);
+    }
+    errors
+}
+
 fn check_consistent_w_trait_requirements<'tcx, P: Property>(
     tcx: TyCtxt<'tcx>,
     func: &LocallyReachable,
@@ -271,34 +559,36 @@ fn is_impl_of_trait(tcx: TyCtxt, owner: LocalDefId) -> Option<DefId> {
     }
 }
 
-fn only_unjustified_axioms<'tcx, P: Property>(
-    tcx: TyCtxt<'tcx>,
-    property: P,
-) -> impl Fn(&FoundAxiom<'tcx, P::Axiom>) -> bool {
-    move |axiom| {
-        log::debug!("getting seeing if axiom {axiom:?} has justification");
-        parse_expr(tcx, axiom.found_in, property).is_none()
-    }
-}
-
 enum JustificationStatus<'tcx, P: Property> {
     AllCallsJustified,
     SomeNotJustified(CallsWObligations),
     ImproperJustification(LocalError<'tcx, P>),
 }
 
-/// Filter a set of calls to a function for only those which are not property justified.
+/// Filter a set of calls to a function for only those which are not property justified,
+/// recording the justifications of those which are.
 fn only_unjustified_callsites<'tcx, P: Property>(
     tcx: TyCtxt<'tcx>,
     in_fn: LocallyReachable,
     property: P,
-) -> impl Fn(CallsWObligations) -> JustificationStatus<'tcx, P> {
-    move |mut calls| {
+) -> impl Fn(CallsWObligations, &mut Vec<Justification>) -> JustificationStatus<'tcx, P> {
+    move |mut calls, justifications| {
         let mut new_spans = Vec::new();
 
         for call_span in calls.from_spans {
-            let call_expr = expr::find_expr_for_call(tcx, calls.call_to, in_fn.reach, call_span);
-            let callsite_annotation = parse_expr(tcx, call_expr, property);
+            // Drops don't have their own expression, so look for a justification on whatever
+            // expression the drop happens within.
+            let call_expr = match calls.kind {
+                CallKind::Call => Some(expr::find_expr_for_call(
+                    tcx,
+                    calls.call_to,
+                    in_fn.reach,
+                    call_span,
+                )),
+                CallKind::Drop => expr::find_expr_containing(tcx, in_fn.reach, call_span),
+            };
+            let callsite_annotation =
+                call_expr.and_then(|call_expr| parse_expr(tcx, call_expr, property));
 
             match callsite_annotation {
                 Some(annotation) => {
@@ -311,6 +601,7 @@ fn only_unjustified_callsites<'tcx, P: Property>(
                     ) {
                         return JustificationStatus::ImproperJustification(e);
                     }
+                    justifications.push(Justification::new(call_span, &annotation));
                 }
                 None => {
                     // Callsite not annotated, add to list of unjustified calls
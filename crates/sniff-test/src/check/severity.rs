@@ -0,0 +1,62 @@
+//! How seriously to take each kind of error, so that e.g. axioms can fail the check while
+//! mismatched obligation names are only warned about during a migration.
+//!
+//! Severities are the default levels of the `sniff_tool::*` lints, see [`super::lints`].
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use rustc_session::{config::Options, lint::Level};
+use serde::{Deserialize, Serialize};
+
+use crate::{SniffTestArgs, check::FindingKind, config::Config};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Ignore it entirely.
+    Allow,
+    /// Report it, but don't fail the check.
+    Warn,
+    /// Fail the check.
+    Deny,
+}
+
+impl Severity {
+    fn level(self) -> Level {
+        match self {
+            Severity::Allow => Level::Allow,
+            Severity::Warn => Level::Warn,
+            Severity::Deny => Level::Deny,
+        }
+    }
+}
+
+/// The severity of each kind of error, from the command line or else the config.
+fn severities(config: &Config, args: &SniffTestArgs) -> BTreeMap<FindingKind, Severity> {
+    let mut severities = config.severity.clone();
+    // If a kind is given more than once, the strictest wins.
+    for (kinds, severity) in [
+        (&args.allow, Severity::Allow),
+        (&args.warn, Severity::Warn),
+        (&args.deny, Severity::Deny),
+    ] {
+        for kind in kinds {
+            severities.insert(*kind, severity);
+        }
+    }
+    severities
+}
+
+/// Set the level of each kind's lint according to its severity, as if with `-A`/`-W`/`-D`.
+///
+/// These go before any other lint flags, so that flags passed to rustc directly (e.g. in
+/// `RUSTFLAGS`) and lint attributes in the source can still override them.
+pub fn set_lint_levels(opts: &mut Options, config: &Config, args: &SniffTestArgs) {
+    let levels = severities(config, args)
+        .into_iter()
+        .map(|(kind, severity)| (kind.lint().name_lower(), severity.level()));
+    opts.lint_opts.splice(0..0, levels);
+}
@@ -0,0 +1,317 @@
+//! Suggested annotations that would fix (or at least start fixing) an error.
+//!
+//! Suggestions are skeletons with `TODO`s for the user to fill in, as we can't know *why*
+//! something is fine, only where that reasoning should be written down.
+
+use regex::Regex;
+use rustc_hir::{BlockCheckMode, Expr, ExprKind, Node, StmtKind, def_id::LocalDefId};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{BytePos, Span};
+
+use crate::{
+    check::{expr, is_impl_of_trait},
+    properties::{FoundAxiom, Property},
+    reachability::{self, CallKind, CallsWObligations},
+};
+
+/// Code to insert at one or more points in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    /// The empty spans to insert code at, and the code to insert there.
+    pub parts: Vec<(Span, String)>,
+}
+
+/// Suggested justifications for the uses within a function.
+pub struct Justifications {
+    pub suggestions: Vec<Suggestion>,
+    /// Whether every use has a suggestion, as some can't be justified without restructuring
+    /// the code (e.g. those in macro expansions).
+    pub all_placed: bool,
+}
+
+/// Something used within a statement that needs to be justified.
+struct Use {
+    description: String,
+    conditions: Vec<String>,
+}
+
+/// Suggest justification comments for some unjustified axioms and calls within `in_fn`,
+/// with one suggestion per statement that needs justifying.
+pub fn justify_uses<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    property: P,
+    in_fn: LocalDefId,
+    axioms: &[FoundAxiom<'tcx, P::Axiom>],
+    calls: &[CallsWObligations],
+) -> Justifications {
+    // The statements that need justifying, along with what they use.
+    let mut statements: Vec<(Span, Vec<Use>)> = Vec::new();
+    let mut all_placed = true;
+    let mut add_use = |expr: Option<&'tcx Expr<'tcx>>, used: Use| {
+        let Some(statement) = expr.and_then(|expr| statement_containing(tcx, expr)) else {
+            all_placed = false;
+            return;
+        };
+        match statements.iter_mut().find(|(s, _)| *s == statement) {
+            Some((_, uses)) => uses.push(used),
+            None => statements.push((statement, vec![used])),
+        }
+    };
+
+    for axiom in axioms {
+        let used = Use {
+            description: format!("the {}", axiom.axiom),
+            conditions: Vec::new(),
+        };
+        add_use(Some(axiom.found_in), used);
+    }
+
+    for calls in calls {
+        let description = format!("the call to `{}`", tcx.def_path_str(calls.call_to));
+        let conditions = calls
+            .conditions
+            .iter()
+            .map(|condition| condition.node.name.clone())
+            .collect::<Vec<_>>();
+        for span in &calls.from_spans {
+            let call_expr = match calls.kind {
+                CallKind::Call => Some(expr::find_expr_for_call(tcx, calls.call_to, in_fn, *span)),
+                CallKind::Drop => expr::find_expr_containing(tcx, in_fn, *span),
+            };
+            let used = Use {
+                description: description.clone(),
+                conditions: conditions.clone(),
+            };
+            add_use(call_expr, used);
+        }
+    }
+
+    let suggestions = statements
+        .into_iter()
+        .map(|(statement, uses)| {
+            let mut descriptions = Vec::new();
+            for used in &uses {
+                if !descriptions.contains(&used.description) {
+                    descriptions.push(used.description.clone());
+                }
+            }
+
+            let marker = property.callsite_marker();
+            let conditions = uses
+                .iter()
+                .flat_map(|used| used.conditions.iter().map(String::as_str));
+            let lines = bullet_lines(conditions, '-').map_or_else(
+                || vec![format!("{marker}: TODO")],
+                |bullets| {
+                    std::iter::once(format!("{marker}:"))
+                        .chain(bullets)
+                        .collect()
+                },
+            );
+
+            Suggestion {
+                message: format!("justify {}", descriptions.join(" and ")),
+                parts: vec![(
+                    statement.shrink_to_lo(),
+                    doc_comment_lines(tcx, statement, &lines),
+                )],
+            }
+        })
+        .collect();
+
+    Justifications {
+        suggestions,
+        all_placed,
+    }
+}
+
+/// Suggest annotating `func` with a skeleton section for `property` (and any keyword it
+/// needs), including the conditions of any `calls` it would then pass on to its own callers.
+pub fn annotate_fn<P: Property>(
+    tcx: TyCtxt,
+    property: P,
+    func: LocalDefId,
+    calls: &[CallsWObligations],
+) -> Option<Suggestion> {
+    if !can_forward_obligations(tcx, func) {
+        return None;
+    }
+
+    let def_span = tcx.def_span(func);
+    if def_span.from_expansion() {
+        return None;
+    }
+
+    let section = property.fn_def_section();
+    let conditions = calls
+        .iter()
+        .flat_map(|calls| &calls.conditions)
+        .map(|condition| condition.node.name.as_str());
+    let lines = std::iter::once(format!("# {section}"))
+        .chain(bullet_lines(conditions, '*').unwrap_or_else(|| vec!["TODO".to_owned()]))
+        .collect::<Vec<_>>();
+
+    let mut parts = vec![(
+        def_span.shrink_to_lo(),
+        doc_comment_lines(tcx, def_span, &lines),
+    )];
+    if let Some(keyword) = property.fn_def_keyword() {
+        parts.extend(insert_keyword(tcx, def_span, keyword));
+    }
+
+    Some(Suggestion {
+        message: format!(
+            "annotate {} with its own `{section}` section",
+            tcx.def_path_str(func.to_def_id())
+        ),
+        parts,
+    })
+}
+
+/// Whether `func` could be annotated to pass obligations on to its callers.
+pub fn can_forward_obligations(tcx: TyCtxt, func: LocalDefId) -> bool {
+    let def_id = func.to_def_id();
+    // Closures can't be annotated, the obligations on trait methods come from the trait,
+    // and `main` and tests have to be callable without any.
+    let is_main = tcx.entry_fn(()).is_some_and(|(main, _)| main == def_id);
+    !(tcx.is_closure_like(def_id)
+        || is_impl_of_trait(tcx, func).is_some()
+        || is_main
+        || reachability::is_test_fn(tcx, func))
+}
+
+/// Suggest adding a qualifier `keyword` (e.g. `unsafe`) to the definition of `func`.
+pub fn add_keyword(tcx: TyCtxt, func: LocalDefId, keyword: &str) -> Option<Suggestion> {
+    let def_span = tcx.def_span(func);
+    if def_span.from_expansion() {
+        return None;
+    }
+
+    Some(Suggestion {
+        message: format!(
+            "add the `{keyword}` keyword to {}",
+            tcx.def_path_str(func.to_def_id())
+        ),
+        parts: vec![insert_keyword(tcx, def_span, keyword)?],
+    })
+}
+
+/// Whether the definition of `func` already has a qualifier `keyword`.
+pub fn has_keyword(tcx: TyCtxt, func: LocalDefId, keyword: &str) -> bool {
+    let sig_span = tcx.def_span(func);
+    fn_qualifiers(tcx, sig_span)
+        .is_some_and(|(qualifiers, _)| qualifiers.split_whitespace().any(|word| word == keyword))
+}
+
+/// Insert a qualifier `keyword` into the function signature at `sig_span`, if it's not
+/// already there.
+fn insert_keyword(tcx: TyCtxt, sig_span: Span, keyword: &str) -> Option<(Span, String)> {
+    let (qualifiers, fn_token) = fn_qualifiers(tcx, sig_span)?;
+    if qualifiers.split_whitespace().any(|word| word == keyword) {
+        return None;
+    }
+
+    let offset = u32::try_from(fn_token).ok()?;
+    let at = sig_span.lo() + BytePos(offset);
+    Some((sig_span.with_lo(at).shrink_to_lo(), format!("{keyword} ")))
+}
+
+/// The text of the qualifiers (and visibility) before the `fn` in a signature, and the offset
+/// into the signature where new qualifiers should go.
+fn fn_qualifiers(tcx: TyCtxt, sig_span: Span) -> Option<(String, usize)> {
+    let snippet = tcx.sess.source_map().span_to_snippet(sig_span).ok()?;
+    // Qualifiers like `unsafe` go after `const` and `async`, but before any `extern` ABI.
+    let fn_token = Regex::new(r"\b(extern|fn)\b").unwrap().find(&snippet)?;
+    Some((snippet[..fn_token.start()].to_owned(), fn_token.start()))
+}
+
+/// A `{bullet} name: TODO` line for each unique condition name, or `None` if there are none.
+fn bullet_lines<'a>(names: impl Iterator<Item = &'a str>, bullet: char) -> Option<Vec<String>> {
+    let mut unique = Vec::new();
+    for name in names {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+
+    (!unique.is_empty()).then(|| {
+        unique
+            .into_iter()
+            .map(|name| format!("{bullet} {name}: TODO"))
+            .collect()
+    })
+}
+
+/// Format `lines` as doc comments to insert before the code at `span`, matching its indentation.
+fn doc_comment_lines(tcx: TyCtxt, span: Span, lines: &[String]) -> String {
+    let before = tcx
+        .sess
+        .source_map()
+        .span_to_prev_source(span)
+        .unwrap_or_default();
+    let line = before.rsplit('\n').next().unwrap_or_default();
+    let indent = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect::<String>();
+
+    let mut comment = String::new();
+    for line in lines {
+        comment.push_str("/// ");
+        comment.push_str(line);
+        comment.push('\n');
+        comment.push_str(&indent);
+    }
+    comment
+}
+
+/// Find the span of the statement (or block tail expression) that a justification for
+/// `expr` should be placed on.
+///
+/// We look through `unsafe` blocks, so the justification goes above the block rather than
+/// inside it, but not out of closures, whose bodies are checked on their own.
+fn statement_containing<'tcx>(tcx: TyCtxt<'tcx>, expr: &'tcx Expr<'tcx>) -> Option<Span> {
+    let mut statement = (expr.span, Some(expr));
+    for (_, node) in tcx.hir_parent_iter(expr.hir_id) {
+        match node {
+            Node::Expr(Expr {
+                kind: ExprKind::Closure(closure),
+                ..
+            }) => {
+                // Attributes aren't allowed on a closure body that's just an expression, so
+                // there'd be nowhere to put the justification without adding braces.
+                if tcx.hir_body(closure.body).value.span == statement.0 {
+                    return None;
+                }
+                break;
+            }
+            Node::Stmt(stmt) => {
+                let stmt_expr = match stmt.kind {
+                    StmtKind::Expr(expr) | StmtKind::Semi(expr) => Some(expr),
+                    StmtKind::Let(_) | StmtKind::Item(_) => None,
+                };
+                statement = (stmt.span, stmt_expr);
+                break;
+            }
+            Node::Block(block) if !matches!(block.rules, BlockCheckMode::UnsafeBlock(_)) => break,
+            Node::Expr(expr) => statement = (expr.span, Some(expr)),
+            Node::Item(_) | Node::ImplItem(_) | Node::TraitItem(_) => break,
+            _ => {}
+        }
+    }
+
+    let (span, expr) = statement;
+    // An attribute before something like `a + b` would be on `a` alone, which isn't allowed.
+    let starts_with_operand = expr.is_some_and(|expr| {
+        matches!(
+            expr.kind,
+            ExprKind::Binary(..)
+                | ExprKind::Assign(..)
+                | ExprKind::AssignOp(..)
+                | ExprKind::Cast(..)
+        )
+    });
+
+    (!span.from_expansion() && !starts_with_operand).then_some(span)
+}
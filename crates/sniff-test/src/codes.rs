@@ -0,0 +1,117 @@
+//! Stable codes for each kind of error we report, with long-form explanations available through
+//! `cargo sniff-test --explain <CODE>`.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::FindingKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum ErrorCode {
+    UnjustifiedAxiom,
+    UnjustifiedCall,
+    UnjustifiedDrop,
+    MissedObligations,
+    MissingKeyword,
+    TraitInconsistency,
+    /// A fine-grained annotation without any conditions we could find.
+    NoConditions,
+    /// A fine-grained annotation with a condition we couldn't parse.
+    MalformedCondition,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 8] = [
+        ErrorCode::UnjustifiedAxiom,
+        ErrorCode::UnjustifiedCall,
+        ErrorCode::UnjustifiedDrop,
+        ErrorCode::MissedObligations,
+        ErrorCode::MissingKeyword,
+        ErrorCode::TraitInconsistency,
+        ErrorCode::NoConditions,
+        ErrorCode::MalformedCondition,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::UnjustifiedAxiom => "ST0001",
+            ErrorCode::UnjustifiedCall => "ST0002",
+            ErrorCode::UnjustifiedDrop => "ST0003",
+            ErrorCode::MissedObligations => "ST0004",
+            ErrorCode::MissingKeyword => "ST0005",
+            ErrorCode::TraitInconsistency => "ST0006",
+            ErrorCode::NoConditions => "ST0007",
+            ErrorCode::MalformedCondition => "ST0008",
+        }
+    }
+
+    /// The long-form explanation, with an example of the error and how to fix it.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ErrorCode::UnjustifiedAxiom => include_str!("codes/ST0001.md"),
+            ErrorCode::UnjustifiedCall => include_str!("codes/ST0002.md"),
+            ErrorCode::UnjustifiedDrop => include_str!("codes/ST0003.md"),
+            ErrorCode::MissedObligations => include_str!("codes/ST0004.md"),
+            ErrorCode::MissingKeyword => include_str!("codes/ST0005.md"),
+            ErrorCode::TraitInconsistency => include_str!("codes/ST0006.md"),
+            ErrorCode::NoConditions => include_str!("codes/ST0007.md"),
+            ErrorCode::MalformedCondition => include_str!("codes/ST0008.md"),
+        }
+    }
+
+    /// A note pointing to the explanation, for diagnostics with this code.
+    pub fn note(self) -> String {
+        format!("for more information about this error, try `cargo sniff-test --explain {self}`")
+    }
+}
+
+impl FindingKind {
+    pub fn code(self) -> ErrorCode {
+        match self {
+            FindingKind::UnjustifiedAxiom => ErrorCode::UnjustifiedAxiom,
+            FindingKind::UnjustifiedCall => ErrorCode::UnjustifiedCall,
+            FindingKind::UnjustifiedDrop => ErrorCode::UnjustifiedDrop,
+            FindingKind::MissedObligations => ErrorCode::MissedObligations,
+            FindingKind::MissingKeyword => ErrorCode::MissingKeyword,
+            FindingKind::TraitInconsistency => ErrorCode::TraitInconsistency,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ErrorCode::ALL
+            .into_iter()
+            .find(|code| code.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown error code `{s}` (expected one of {})",
+                    ErrorCode::ALL.map(ErrorCode::as_str).join(", ")
+                )
+            })
+    }
+}
+
+impl From<ErrorCode> for String {
+    fn from(code: ErrorCode) -> Self {
+        code.as_str().to_owned()
+    }
+}
+
+impl TryFrom<String> for ErrorCode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
@@ -0,0 +1,67 @@
+//! Configuration for sniff-test itself, from the `[sniff-test]` table of `sniff-test.toml`.
+//!
+//! ```toml
+//! [sniff-test.severity]
+//! unjustified-axiom = "deny"
+//! missed-obligations = "warn"
+//!
+//! [sniff-test.dependencies]
+//! our-internal-crate = "verify"
+//! crate-under-review = "find"
+//! ```
+
+use std::collections::BTreeMap;
+
+use rustc_session::{EarlyDiagCtxt, config::Options};
+use serde::Deserialize;
+
+use crate::{
+    DependenciesPosture,
+    annotations::toml::{CONFIG_TABLE, FILE_NAME},
+    check::{FindingKind, severity::Severity},
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How seriously to take each kind of error, where it isn't overridden on the command line.
+    pub severity: BTreeMap<FindingKind, Severity>,
+    /// How to handle particular dependencies, where it isn't overridden on the command line.
+    pub dependencies: BTreeMap<String, DependenciesPosture>,
+}
+
+impl Config {
+    /// Load the configuration, warning and using the defaults if we can't.
+    ///
+    /// This happens before the compiler session exists, so only needs its `opts`.
+    pub fn load(opts: &Options) -> Self {
+        match Self::from_file(FILE_NAME) {
+            Ok(config) => config,
+            Err(e) => {
+                EarlyDiagCtxt::new(opts.error_format).early_warn(format!(
+                    "Failed to parse the `[{CONFIG_TABLE}]` table of {FILE_NAME}: {e:#}"
+                ));
+                Config::default()
+            }
+        }
+    }
+
+    /// Load the configuration without warning if we can't, for when the drivers will warn anyway.
+    pub fn load_quietly() -> Self {
+        Self::from_file(FILE_NAME).unwrap_or_default()
+    }
+
+    fn from_file(path: &str) -> anyhow::Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut table = toml::from_str::<toml::Table>(&text)?;
+        match table.remove(CONFIG_TABLE) {
+            Some(config) => Ok(config.try_into()?),
+            None => Ok(Config::default()),
+        }
+    }
+}
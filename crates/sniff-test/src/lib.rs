@@ -15,12 +15,14 @@
 )]
 
 extern crate lazy_static;
+extern crate rustc_abi;
 extern crate rustc_ast;
 extern crate rustc_driver;
 extern crate rustc_errors;
 extern crate rustc_hir;
 extern crate rustc_index;
 extern crate rustc_interface;
+extern crate rustc_lint;
 extern crate rustc_middle;
 extern crate rustc_public;
 extern crate rustc_query_system;
@@ -30,19 +32,35 @@ extern crate rustc_type_ir;
 
 pub mod annotations;
 mod check;
+mod codes;
+mod config;
 pub mod properties;
 mod reachability;
+mod report;
+mod summary;
 pub mod utils;
 
-use std::{borrow::Cow, env, process::Command, sync::Mutex};
+use std::{
+    borrow::Cow,
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use rustc_hir::def_id::LOCAL_CRATE;
 use rustc_middle::ty::TyCtxt;
 use rustc_plugin::{CrateFilter, RustcPlugin, RustcPluginArgs, Utf8Path};
 use serde::{Deserialize, Serialize};
 
-use crate::check::check_crate_for_property;
+use crate::{
+    check::{CheckResults, FindingKind, LocalError, check_crate_for_property},
+    codes::ErrorCode,
+    properties::Property,
+};
+
+pub use crate::summary::cli_main;
 
 // This struct is the plugin provided to the rustc_plugin framework,
 // and it must be exported for use by the CLI/driver binaries.
@@ -50,11 +68,18 @@ pub struct PrintAllItemsPlugin;
 
 // To parse CLI arguments, we use Clap for this example. But that
 // detail is up to you.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Parser, Serialize, Deserialize, Default, Clone, Debug)]
 pub struct SniffTestArgs {
     /// How to handle this workspace's dependencies.
-    #[arg(short, long)]
-    dependencies: DependenciesPosture,
+    // Not needed just to `--explain` an error code.
+    #[arg(short, long, value_enum, required_unless_present = "explain")]
+    dependencies: Option<DependenciesPosture>,
+
+    /// How to handle a particular dependency, overriding `--dependencies` and `sniff-test.toml`
+    /// for that crate. Can be given more than once.
+    #[arg(long, value_name = "CRATE=POSTURE", value_parser = parse_dependency_posture)]
+    dependency_posture: Vec<(String, DependenciesPosture)>,
 
     #[arg(short, long)]
     /// LEGACY ARG (i'm keeping it around to be faster, will remove later):
@@ -67,19 +92,125 @@ pub struct SniffTestArgs {
     #[arg(short, long)]
     buzzword_checking: bool,
 
+    /// How to handle calls whose callee can't be statically known (`fn` pointers, `dyn Trait`, etc.).
+    #[arg(long, value_enum, default_value_t)]
+    indirect_calls: IndirectCallsPosture,
+
+    /// Additional kinds of functions to use as entry points for analysis.
+    ///
+    /// These can also be set with a crate-wide `#![sniff_tool::entry_points(..)]` attribute.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    entry_points: Vec<EntrySelector>,
+
+    /// Write a versioned JSON report of each checked crate into this directory (or to stdout if `-`).
+    #[arg(long, value_name = "DIR")]
+    report_json: Option<PathBuf>,
+
+    /// Write a SARIF 2.1.0 log of each checked crate's errors into this directory (or to stdout if `-`).
+    #[arg(long, value_name = "DIR")]
+    report_sarif: Option<PathBuf>,
+
+    /// Write a self-contained HTML report with an interactive call graph of each checked crate
+    /// into this directory (or to stdout if `-`).
+    #[arg(long, value_name = "DIR")]
+    report_html: Option<PathBuf>,
+
+    /// Dump the call graph of each checked crate in this format, to stdout unless
+    /// `--callgraph-dir` is given.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    dump_callgraph: Option<CallGraphFormat>,
+
+    /// Write `--dump-callgraph` output into this directory instead of to stdout.
+    #[arg(long, value_name = "DIR", requires = "dump_callgraph")]
+    callgraph_dir: Option<PathBuf>,
+
+    /// Write a Markdown inventory of each checked crate's functions with obligations, and
+    /// everything that relies on them, into this directory (or to stdout if `-`).
+    #[arg(long, value_name = "DIR")]
+    audit_md: Option<PathBuf>,
+
+    /// Write a Markdown report of the unsafe functions from other crates that each checked crate
+    /// calls, grouped by the crate they're from, into this directory (or to stdout if `-`).
+    #[arg(long, value_name = "DIR")]
+    unsafe_surface: Option<PathBuf>,
+
+    /// Print the conditions each failing function would need to pass its obligations on to its
+    /// callers, including those inherited through other local functions.
+    #[arg(long)]
+    infer_conditions: bool,
+
+    /// Edit the source to add skeleton annotations and justifications for every error we can.
+    #[arg(long)]
+    fix: bool,
+
+    /// Ignore these kinds of error entirely, overriding `sniff-test.toml`.
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KIND")]
+    allow: Vec<FindingKind>,
+
+    /// Only warn about these kinds of error, overriding `sniff-test.toml`.
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KIND")]
+    warn: Vec<FindingKind>,
+
+    /// Fail on these kinds of error, overriding `sniff-test.toml`. This is the default for every
+    /// kind of error, and takes precedence if a kind is given to more than one of these flags.
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KIND")]
+    deny: Vec<FindingKind>,
+
+    /// Only fail on errors that aren't recorded in this baseline file, noting any recorded errors
+    /// that have since been fixed.
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Record every current error in the `--baseline` file instead of failing on them.
+    #[arg(long, requires = "baseline")]
+    write_baseline: bool,
+
+    /// Only fail on errors in functions changed since this git revision, or in calls to functions
+    /// whose annotations changed. Everything is still checked, so this finds the same errors in
+    /// that code as a full check would.
+    #[arg(long, value_name = "REV")]
+    changed_since: Option<String>,
+
+    /// Keep analysing the crates that depend on a failing crate, reporting its errors as warnings
+    /// so it still compiles, and only fail once every crate has been analysed.
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Print a detailed explanation of an error code (e.g. `ST0001`) instead of checking anything.
+    #[arg(long, value_name = "CODE")]
+    explain: Option<ErrorCode>,
+
+    #[command(subcommand)]
+    command: Option<SniffTestCommand>,
+
     #[clap(last = true)]
     cargo_args: Vec<String>,
 }
 
-#[derive(ValueEnum, Clone, Debug, Default, Serialize, Deserialize)]
+/// Things to do other than just checking crates.
+#[derive(Subcommand, Serialize, Deserialize, Clone, Debug)]
+enum SniffTestCommand {
+    /// Explain how a function is reachable, by listing the paths to it from every entry point
+    /// along with the annotations found on each.
+    Explain {
+        /// The path of the function, as it appears in our errors (e.g. `my_mod::my_fn`).
+        def_path: String,
+        /// The most paths to list.
+        #[arg(long, default_value_t = 20)]
+        max_paths: usize,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 enum DependenciesPosture {
     #[default]
     /// Trust that dependencies have been properly documented with regard to the desired properties.
     ///
     /// *"I trust them"*
     Trust,
-    /// Analyze the **used** public functions of all transitive dependencies, flagging potential issues
-    /// to be fixed at the boundary of the current workspace.
+    /// Analyze the public functions of all transitive dependencies, reporting the issues found
+    /// as warnings that don't fail the sniff test.
     ///
     /// *"I don't care if their code is correct, I just want to make sure how I'm using it is fine."*
     Find,
@@ -90,6 +221,84 @@ enum DependenciesPosture {
     Verify,
 }
 
+/// The environment variable identifying runs that have to check every crate again.
+const RUN_ENV: &str = "SNIFF_TEST_RUN";
+
+impl SniffTestArgs {
+    /// Whether checking a crate does more than we can replay from its summary when cargo
+    /// considers it fresh, like writing reports or explaining a function.
+    fn has_output(&self) -> bool {
+        self.command.is_some()
+            || self.report_json.is_some()
+            || self.report_sarif.is_some()
+            || self.report_html.is_some()
+            || self.dump_callgraph.is_some()
+            || self.audit_md.is_some()
+            || self.unsafe_surface.is_some()
+            || self.infer_conditions
+            || self.fix
+            || self.write_baseline
+    }
+
+    /// How to handle the dependency `krate`, from `--dependency-posture` (or `sniff-test.toml`)
+    /// if it's given for that crate, or else `--dependencies`.
+    fn dependency_posture(&self, krate: &str) -> DependenciesPosture {
+        self.dependency_posture
+            .iter()
+            .rev()
+            .find(|(name, _)| name == krate)
+            .map_or(self.dependencies.unwrap_or_default(), |(_, posture)| {
+                *posture
+            })
+    }
+}
+
+/// Parse a `CRATE=POSTURE` pair, normalizing the crate name as rustc does.
+fn parse_dependency_posture(s: &str) -> Result<(String, DependenciesPosture), String> {
+    let (krate, posture) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `CRATE=POSTURE`, found `{s}`"))?;
+    let posture = DependenciesPosture::from_str(posture, true)?;
+    Ok((krate.replace('-', "_"), posture))
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndirectCallsPosture {
+    #[default]
+    /// Only follow calls whose callee is known statically.
+    ///
+    /// *"If I can't see it, it isn't there."*
+    Ignore,
+    /// Assume an indirect call can reach any local function it could possibly resolve to: every
+    /// local implementation of a trait method called through `dyn Trait` or a generic, and every
+    /// address-taken local function whose signature matches a called `fn` pointer.
+    ///
+    /// *"If it could be called, assume it is."*
+    Conservative,
+}
+
+/// Kinds of functions that can be selected as entry points, in addition to annotated ones.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntrySelector {
+    /// Exported symbols, i.e. `#[no_mangle]` and `extern "C"` functions.
+    Exported,
+    /// `#[test]` functions.
+    Tests,
+    /// The `main` function of binaries and examples.
+    Main,
+    /// Implementations of public traits, which can be called from outside the crate.
+    TraitImpls,
+}
+
+/// Formats the call graph can be dumped in.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CallGraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// JSON, with separate lists of nodes and edges.
+    Json,
+}
+
 const TO_FILE: bool = false;
 
 pub static ARGS: Mutex<Option<SniffTestArgs>> = Mutex::new(None);
@@ -145,7 +354,38 @@ impl RustcPlugin for PrintAllItemsPlugin {
     // If one of the CLI arguments was a specific file to analyze, then you
     // could provide a different filter.
     fn args(&self, _target_dir: &Utf8Path) -> RustcPluginArgs<Self::Args> {
-        let args = SniffTestArgs::parse_from(env::args());
+        let mut args = SniffTestArgs::parse_from(env::args());
+        if let Some(code) = args.explain {
+            print!("{}", code.explanation());
+            std::process::exit(0);
+        }
+        // The driver for each crate is run from that crate's directory, so make sure report paths
+        // are still relative to where we were invoked.
+        args.report_json = args.report_json.map(absolute_report_dir);
+        args.report_sarif = args.report_sarif.map(absolute_report_dir);
+        args.report_html = args.report_html.map(absolute_report_dir);
+        args.audit_md = args.audit_md.map(absolute_report_dir);
+        args.unsafe_surface = args.unsafe_surface.map(absolute_report_dir);
+        args.callgraph_dir = args.callgraph_dir.map(absolute_report_dir);
+        // Dependencies aren't run from the workspace's directory, so pass them its postures.
+        // Flags come last, so take precedence over the config.
+        let config = crate::config::Config::load_quietly();
+        args.dependency_posture.splice(
+            0..0,
+            config
+                .dependencies
+                .into_iter()
+                .map(|(krate, posture)| (krate.replace('-', "_"), posture)),
+        );
+        if let Some(rev) = &args.changed_since
+            && let Err(e) = crate::check::changed::check_revision(rev)
+        {
+            eprintln!("error: couldn't find the changes since `{rev}`: {e:#}");
+            std::process::exit(1);
+        }
+        args.baseline = args
+            .baseline
+            .map(|file| std::path::absolute(&file).unwrap_or(file));
         let filter = CrateFilter::AllCrates;
         RustcPluginArgs { args, filter }
     }
@@ -153,6 +393,13 @@ impl RustcPlugin for PrintAllItemsPlugin {
     // Pass Cargo arguments (like --feature) from the top-level CLI to Cargo.
     fn modify_cargo(&self, cargo: &mut Command, args: &Self::Args) {
         log::debug!("modifying cargo args");
+        summary::modify_cargo(cargo, &args.cargo_args);
+        if args.has_output() {
+            let run = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            cargo.env(RUN_ENV, run.as_nanos().to_string());
+        }
         cargo.args(&args.cargo_args);
 
         // if args.release {
@@ -194,6 +441,14 @@ impl RustcPlugin for PrintAllItemsPlugin {
     }
 }
 
+fn absolute_report_dir(dir: PathBuf) -> PathBuf {
+    if dir.as_os_str() == "-" {
+        dir
+    } else {
+        std::path::absolute(&dir).unwrap_or(dir)
+    }
+}
+
 #[allow(dead_code)]
 struct PrintAllItemsCallbacks {
     args: Option<SniffTestArgs>,
@@ -225,6 +480,64 @@ fn is_dependency(compiler_args: &[String]) -> bool {
         .expect("shouldn't have an empty string here")
 }
 
+/// Write every report asked for in `args` about the current crate.
+fn write_reports<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    args: &SniffTestArgs,
+    property: P,
+    is_dependency: bool,
+    results: &CheckResults<'tcx, P>,
+) {
+    if let Some(dir) = &args.report_json {
+        report::json::write(tcx, dir, is_dependency, results);
+    }
+    if let Some(dir) = &args.report_sarif {
+        report::sarif::write(tcx, dir, results);
+    }
+    if let Some(dir) = &args.report_html {
+        report::html::write(tcx, dir, results);
+    }
+    if let Some(format) = args.dump_callgraph {
+        let dir = args.callgraph_dir.as_deref().unwrap_or(Path::new("-"));
+        report::callgraph::write(tcx, dir, format, property, results);
+    }
+    if let Some(dir) = &args.audit_md {
+        report::audit::write(tcx, dir, property, results);
+    }
+    if let Some(dir) = &args.unsafe_surface {
+        report::surface::write(tcx, dir, results);
+    }
+}
+
+/// Drop the `errors` recorded in the `--baseline`, and fix what we can with `--fix`, returning the
+/// errors that are left along with how many were fixed.
+fn baseline_and_fix<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    args: &SniffTestArgs,
+    property: P,
+    is_dependency: bool,
+    errors: Vec<LocalError<'tcx, P>>,
+) -> (Vec<LocalError<'tcx, P>>, usize) {
+    let errors = match &args.baseline {
+        Some(file) if args.write_baseline => {
+            crate::check::baseline::write(tcx, file, &errors);
+            Vec::new()
+        }
+        Some(file) => crate::check::baseline::apply(tcx, file, errors),
+        None => errors,
+    };
+
+    // Never edit the source of dependencies, which may well be in the cargo registry.
+    if args.fix && !is_dependency && !errors.is_empty() {
+        let found = errors.len();
+        let unfixed = crate::check::fix::apply_fixes(tcx, property, errors);
+        let fixed = found - unfixed.len();
+        (unfixed, fixed)
+    } else {
+        (errors, 0)
+    }
+}
+
 // FIXME: move to check submodule
 fn analyze_crate(
     tcx: TyCtxt,
@@ -232,36 +545,127 @@ fn analyze_crate(
     is_dependency: bool,
     args: &SniffTestArgs,
 ) -> rustc_driver::Compilation {
-    match (is_dependency, &args.dependencies) {
-        // If we're not a dependency, or we are but we're verifying them -> run full analysis
-        (false, _) | (true, DependenciesPosture::Verify) => {
+    let posture = if is_dependency {
+        args.dependency_posture(crate_name.as_str())
+    } else {
+        DependenciesPosture::Verify
+    };
+    match posture {
+        // Analyse crates we're verifying or finding issues in (including all local crates).
+        DependenciesPosture::Verify | DependenciesPosture::Find => {
+            // Issues in crates we're only finding issues in don't fail the check.
+            let find_only = matches!(posture, DependenciesPosture::Find);
             let property = properties::SafetyProperty;
-            let stats = match check_crate_for_property(tcx, property, is_dependency) {
-                Ok(stats) => stats,
-                Err(local_err) => {
-                    crate::check::err::report_errors(tcx, property, local_err);
-                    println!("the {crate_name} crate FAILED the sniff test");
-                    return rustc_driver::Compilation::Stop;
-                }
+            let mut results = check_crate_for_property(tcx, property, is_dependency);
+
+            if let Some(SniffTestCommand::Explain {
+                def_path,
+                max_paths,
+            }) = &args.command
+            {
+                crate::check::explain::explain(tcx, property, &results, def_path, *max_paths);
+                return rustc_driver::Compilation::Continue;
+            }
+
+            if let Some(rev) = &args.changed_since {
+                crate::check::changed::retain_changed(tcx, rev, &mut results);
+            }
+
+            // Reports only include the errors that are left to fail the check, like its exit status.
+            let errors = std::mem::take(&mut results.errors);
+            let (errors, fixed) = baseline_and_fix(tcx, args, property, is_dependency, errors);
+            results.errors = errors;
+
+            write_reports(tcx, args, property, is_dependency, &results);
+            if args.infer_conditions {
+                crate::check::infer::print_inferred_conditions(tcx, property, &results);
+            }
+
+            crate::check::lints::emit(tcx, property, results.warnings);
+            crate::check::lints::emit(tcx, property, results.allowed);
+            let errors = results.errors;
+
+            let failures = if find_only {
+                0
+            } else {
+                errors.iter().map(LocalError::issue_count).sum()
             };
+            summary::record(tcx, is_dependency, &results.stats, failures, fixed);
+
+            if find_only && !errors.is_empty() {
+                let why = "this dependency's posture is `find`, so this is only a warning";
+                crate::check::lints::emit_as_warnings(tcx, property, errors, why);
+                println!("found issues in the {crate_name} crate");
+                return rustc_driver::Compilation::Continue;
+            }
+
+            if !errors.is_empty() {
+                let compilation = if args.keep_going {
+                    // The front end still fails at the end, from this crate's summary.
+                    let why =
+                        "this fails the sniff test, but `--keep-going` only warns about it here";
+                    crate::check::lints::emit_as_warnings(tcx, property, errors, why);
+                    rustc_driver::Compilation::Continue
+                } else {
+                    crate::check::lints::emit(tcx, property, errors);
+                    rustc_driver::Compilation::Stop
+                };
+                println!("the {crate_name} crate FAILED the sniff test");
+                return compilation;
+            }
+
+            // The fixes are only skeletons, which still need filling in and checking.
+            if fixed > 0 {
+                println!(
+                    "fixed {fixed} error(s) in the {crate_name} crate, which will be checked on the next run"
+                );
+                return rustc_driver::Compilation::Continue;
+            }
 
             println!(
                 "the {crate_name:^20} crate passes the sniff test!! \t\t(stable id {:16x?}) - {:>5}",
                 tcx.stable_crate_id(LOCAL_CRATE).as_u64(),
                 if is_dependency { "dep" } else { "local" },
             );
-            log::debug!("\tstats for `{crate_name}` are {stats:?}");
-        }
-        (true, DependenciesPosture::Find) => {
-            // find property 'caveats'
-            todo!("do check, but don't error. just write to file for later analysis");
+            log::debug!("\tstats for `{crate_name}` are {:?}", results.stats);
         }
-        (true, DependenciesPosture::Trust) => { /* Nothing to be done! We're trusting :) */ }
+        DependenciesPosture::Trust => { /* Nothing to be done! We're trusting :) */ }
     }
     rustc_driver::Compilation::Continue
 }
 
 impl rustc_driver::Callbacks for PrintAllItemsCallbacks {
+    fn config(&mut self, config: &mut rustc_interface::interface::Config) {
+        let args = self.args.as_ref().unwrap();
+        // Cargo doesn't know our arguments change what we find, so have it treat them like
+        // environment variables the crate depends on. Otherwise it would consider crates checked
+        // with different arguments (or that need checking on every run) fresh, and we'd never run.
+        config.psess_created = Some(Box::new(|psess| {
+            for var in ["PLUGIN_ARGS", RUN_ENV] {
+                let value = std::env::var(var).ok();
+                psess.env_depinfo.get_mut().insert((
+                    rustc_span::Symbol::intern(var),
+                    value.as_deref().map(rustc_span::Symbol::intern),
+                ));
+            }
+        }));
+        config.register_lints = Some(Box::new(crate::check::lints::register_lints));
+        config.override_queries = Some(crate::check::lints::override_queries);
+        let sniff_config = crate::config::Config::load(&config.opts);
+        crate::check::severity::set_lint_levels(&mut config.opts, &sniff_config, args);
+        // Cargo caps the lint levels of dependencies, which would hide their errors when we're
+        // verifying them.
+        let crate_name = config.opts.crate_name.as_deref().unwrap_or_default();
+        if self.is_dependency
+            && matches!(
+                args.dependency_posture(crate_name),
+                DependenciesPosture::Verify
+            )
+        {
+            config.opts.lint_cap = None;
+        }
+    }
+
     // At the top-level, the Rustc API uses an event-based interface for
     // accessing the compiler at different stages of compilation. In this callback,
     // all the type-checking has completed.
@@ -276,11 +680,15 @@ impl rustc_driver::Callbacks for PrintAllItemsCallbacks {
         // your plugin is being invoked on a dependency, then you need to ensure
         // the dependency is type-checked (its .rmeta file is emitted into target/)
         // so that its dependents can read the compiler outputs.
-        analyze_crate(
+        let compilation = analyze_crate(
             tcx,
             crate_name,
             self.is_dependency,
             self.args.as_ref().unwrap(),
-        )
+        );
+        // Only now that all our lints have been emitted can we tell which `#[expect]`s weren't
+        // fulfilled.
+        crate::check::lints::check_expectations(tcx);
+        compilation
     }
 }
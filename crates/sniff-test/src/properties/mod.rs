@@ -29,6 +29,19 @@ pub trait Property: Debug + Copy + 'static {
     /// that indicates obligations have been discharged.
     fn callsite_regex(&self) -> Regex;
 
+    /// The heading of the doc comment section to suggest for function definitions
+    /// (i.e. something matched by [`Property::fn_def_regex`]).
+    fn fn_def_section(&self) -> &'static str;
+
+    /// The marker to suggest for justifying calls (i.e. something matched by
+    /// [`Property::callsite_regex`]).
+    fn callsite_marker(&self) -> &'static str;
+
+    /// The keyword that function definitions annotated with this property need, if any.
+    fn fn_def_keyword(&self) -> Option<&'static str> {
+        None
+    }
+
     fn find_axioms_in_expr<'tcx>(
         &mut self, // TODO: why is this a mutable reference?
         tcx: TyCtxt<'tcx>,
@@ -104,6 +117,12 @@ impl<'tcx, T: Property> Visitor<'tcx> for FinderWrapper<'tcx, T> {
 
     #[allow(clippy::semicolon_if_nothing_returned)]
     fn visit_expr(&mut self, ex: &'tcx rustc_hir::Expr<'tcx>) -> Self::Result {
+        // Closures (and coroutines) are analyzed as their own items, so don't attribute
+        // their axioms to the enclosing function.
+        if let rustc_hir::ExprKind::Closure(_) = ex.kind {
+            return;
+        }
+
         self.axioms
             .extend(self.property.find_axioms_in_expr(self.tcx, self.tychck, ex));
 
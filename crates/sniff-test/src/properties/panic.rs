@@ -31,6 +31,14 @@ impl Property for PanicProperty {
         Regex::new("(\n|^)(\\s*)[#]+ (Panics|PANICS)(\n|$)").unwrap()
     }
 
+    fn fn_def_section(&self) -> &'static str {
+        "Panics"
+    }
+
+    fn callsite_marker(&self) -> &'static str {
+        "PANICS"
+    }
+
     fn find_axioms_in_expr<'tcx>(
         &mut self,
         tcx: TyCtxt<'tcx>,
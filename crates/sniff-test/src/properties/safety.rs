@@ -4,11 +4,12 @@ use regex::Regex;
 use rustc_ast::UnOp;
 use rustc_hir::ExprKind;
 use rustc_middle::ty::TyCtxt;
+use rustc_span::{DUMMY_SP, source_map::respan};
 use rustc_type_ir::TyKind;
 
 use super::Axiom;
 use crate::{
-    annotations::PropertyViolation,
+    annotations::{Condition, PropertyViolation},
     check::LocalError,
     properties::{FoundAxiom, Property},
     reachability::LocallyReachable,
@@ -33,6 +34,18 @@ impl Property for SafetyProperty {
         Regex::new("(\n|^)(\\s*)(Safety|SAFETY):").unwrap()
     }
 
+    fn fn_def_section(&self) -> &'static str {
+        "Safety"
+    }
+
+    fn callsite_marker(&self) -> &'static str {
+        "SAFETY"
+    }
+
+    fn fn_def_keyword(&self) -> Option<&'static str> {
+        Some("unsafe")
+    }
+
     fn find_axioms_in_expr<'tcx>(
         &mut self,
         _tcx: TyCtxt<'tcx>,
@@ -51,6 +64,18 @@ impl Property for SafetyProperty {
             }
         }
 
+        // We can't know what an `unsafe fn` pointer will call, so treat calling it as an axiom.
+        if let ExprKind::Call(callee, _args) = expr.kind
+            && let TyKind::FnPtr(_sig, header) = tyck.expr_ty_adjusted(callee).kind()
+            && header.safety.is_unsafe()
+        {
+            return vec![FoundAxiom {
+                axiom: SafetyAxiom::UnsafeFnPtrCall,
+                span: expr.span,
+                found_in: expr,
+            }];
+        }
+
         vec![]
     }
 
@@ -72,21 +97,38 @@ impl Property for SafetyProperty {
 #[derive(Debug, Clone)]
 pub enum SafetyAxiom {
     RawPtrDeref,
+    UnsafeFnPtrCall,
 }
 
 impl Axiom for SafetyAxiom {
     type Property = SafetyProperty;
 
     fn known_requirements(&self) -> Option<PropertyViolation> {
-        todo!()
-        // match self {
-        //     Self::RawPtrDeref => Some(Badness::ConditionallyBad(
-        //         annotations::Requirement::construct([
-        //             ("ptr-non-null", "the dereferenced pointer must be non-null"),
-        //             ("ptr-aligned", "the dereferenced pointer must be aligned"),
-        //         ]),
-        //     )),
-        // }
+        match self {
+            Self::RawPtrDeref => Some(PropertyViolation::Conditionally(
+                [
+                    ("ptr-non-null", "the dereferenced pointer must be non-null"),
+                    ("ptr-aligned", "the dereferenced pointer must be aligned"),
+                    (
+                        "ptr-valid",
+                        "the dereferenced pointer must point to a valid, initialized value",
+                    ),
+                ]
+                .into_iter()
+                .map(|(name, description)| {
+                    respan(
+                        DUMMY_SP,
+                        Condition {
+                            name: name.to_owned(),
+                            description: description.to_owned(),
+                        },
+                    )
+                })
+                .collect(),
+            )),
+            // Depends entirely on the function being pointed to.
+            Self::UnsafeFnPtrCall => None,
+        }
     }
 }
 
@@ -94,6 +136,7 @@ impl Display for SafetyAxiom {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let name = match self {
             Self::RawPtrDeref => "raw pointer derefence",
+            Self::UnsafeFnPtrCall => "call through an unsafe function pointer",
         };
         f.write_str(name)
     }
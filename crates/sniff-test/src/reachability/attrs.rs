@@ -4,6 +4,7 @@ use std::any::{Any, TypeId};
 
 use rustc_hir::{Attribute, def_id::DefId};
 use rustc_middle::ty::TyCtxt;
+use rustc_span::{Span, Symbol};
 
 use crate::properties::{self, Property};
 
@@ -78,3 +79,21 @@ pub fn get_sniff_tool_attrs<Attr>(
         })
         .collect::<Vec<_>>()
 }
+
+/// Get the arguments of all `#[sniff_tool::{name}(..)]` attributes, along with the span of each.
+///
+/// For example, `#[sniff_tool::{name}(a, b)]` will give `a` and `b`.
+pub fn get_sniff_tool_attr_args(attrs: &[Attribute], name: &str) -> Vec<(String, Span)> {
+    let path = [Symbol::intern("sniff_tool"), Symbol::intern(name)];
+    attrs
+        .iter()
+        .filter(|attr| attr.path_matches(&path))
+        .flat_map(|attr| attr.meta_item_list().unwrap_or_default())
+        .map(|arg| {
+            let name = arg
+                .ident()
+                .map_or_else(String::new, |ident| ident.to_string());
+            (name, arg.span())
+        })
+        .collect()
+}
@@ -1,18 +1,34 @@
 //! Finds the 'bad' functions that should be annotated
 
-use crate::annotations::Obligation;
+use crate::annotations::{AnnotationSource, Condition, Obligation, PropertyViolation};
 use crate::annotations::{parse_fn_def, toml::TomlAnnotation};
 use crate::properties::Property;
 use crate::reachability::LocallyReachable;
 
 use rustc_hir::def_id::DefId;
 use rustc_middle::ty::TyCtxt;
-use rustc_span::Span;
+use rustc_span::{Span, source_map::Spanned};
+use serde::Serialize;
 
-#[derive(Debug)]
+/// How a function ends up being called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    /// Called directly.
+    Call,
+    /// A `Drop::drop` implementation run when a value is dropped.
+    Drop,
+}
+
+#[derive(Debug, Clone)]
 pub struct CallsWObligations {
     pub call_to: DefId,
+    pub kind: CallKind,
     pub obligation: Obligation,
+    /// The conditions listed on the callee, even if we're not checking them individually.
+    pub conditions: Vec<Spanned<Condition>>,
+    /// Where the callee's annotation came from.
+    pub annotation_source: AnnotationSource,
     pub from_spans: Vec<Span>,
 }
 
@@ -20,27 +36,55 @@ fn call_has_obligations<P: Property>(
     tcx: TyCtxt,
     toml_annotations: &TomlAnnotation,
     property: P,
+    kind: CallKind,
 ) -> impl Fn((&DefId, &Vec<Span>)) -> Option<CallsWObligations> {
     move |(to_def_id, from_spans)| {
         let annotation = parse_fn_def(tcx, toml_annotations, *to_def_id, property)?;
+        let conditions = match &annotation.local_violation_annotation {
+            PropertyViolation::Conditionally(conditions) => conditions.clone(),
+            PropertyViolation::Unconditional | PropertyViolation::Never => Vec::new(),
+        };
         annotation
             .creates_obligation()
             .map(|obligation| CallsWObligations {
                 call_to: *to_def_id,
+                kind,
                 obligation,
+                conditions,
+                annotation_source: annotation.source.clone(),
                 from_spans: from_spans.clone(),
             })
     }
 }
 
+/// Find the calls (and drops) in a function to those with obligations, in the order they
+/// appear in the source.
 pub fn find_calls_w_obligations<P: Property>(
     tcx: TyCtxt,
     toml_annotations: &TomlAnnotation,
     locally_reachable: &LocallyReachable,
     property: P,
 ) -> impl Iterator<Item = CallsWObligations> {
-    locally_reachable
+    let calls = locally_reachable
         .calls_to
         .iter()
-        .filter_map(call_has_obligations(tcx, toml_annotations, property))
+        .filter_map(call_has_obligations(
+            tcx,
+            toml_annotations,
+            property,
+            CallKind::Call,
+        ));
+    let drops = locally_reachable
+        .drops
+        .iter()
+        .filter_map(call_has_obligations(
+            tcx,
+            toml_annotations,
+            property,
+            CallKind::Drop,
+        ));
+    let mut all = calls.chain(drops).collect::<Vec<_>>();
+    // Calls are found in a hash map, so sort them to keep our output deterministic.
+    all.sort_by_key(|calls| calls.from_spans.first().copied());
+    all.into_iter()
 }
@@ -0,0 +1,79 @@
+//! Resolves the `Drop::drop` implementations that dropping a value will run.
+
+use std::collections::HashSet;
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{Ty, TyCtxt, TyKind, TypingEnv};
+
+/// Get all the `Drop::drop` implementations that may run when a value of type `ty` is dropped,
+/// walking through its drop glue to find the destructors of any nested fields.
+pub fn destructors_run_by<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    typing_env: TypingEnv<'tcx>,
+    ty: Ty<'tcx>,
+) -> Vec<DefId> {
+    let mut finder = DropGlueWalker {
+        tcx,
+        typing_env,
+        seen: HashSet::new(),
+        destructors: Vec::new(),
+    };
+    finder.walk(ty);
+    finder.destructors
+}
+
+struct DropGlueWalker<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    typing_env: TypingEnv<'tcx>,
+    seen: HashSet<Ty<'tcx>>,
+    destructors: Vec<DefId>,
+}
+
+impl<'tcx> DropGlueWalker<'tcx> {
+    fn walk(&mut self, ty: Ty<'tcx>) {
+        let ty = self
+            .tcx
+            .try_normalize_erasing_regions(self.typing_env, ty)
+            .unwrap_or(ty);
+
+        if !self.seen.insert(ty) || !ty.needs_drop(self.tcx, self.typing_env) {
+            return;
+        }
+
+        match ty.kind() {
+            TyKind::Adt(adt, args) => {
+                if adt.is_manually_drop() {
+                    return;
+                }
+
+                if let Some(destructor) = self.tcx.adt_destructor(adt.did()) {
+                    self.destructors.push(destructor.did);
+                    // We don't look inside the destructors of other crates, so assume that a type
+                    // with its own destructor (e.g. `Vec<T>` or `Box<T>`) drops everything it's
+                    // generic over.
+                    for arg_ty in args.types() {
+                        self.walk(arg_ty);
+                    }
+                }
+
+                for field in adt.all_fields() {
+                    self.walk(field.ty(self.tcx, args));
+                }
+            }
+            TyKind::Tuple(tys) => {
+                for ty in *tys {
+                    self.walk(ty);
+                }
+            }
+            TyKind::Array(ty, _) | TyKind::Slice(ty) | TyKind::Pat(ty, _) => self.walk(*ty),
+            TyKind::Closure(_, args) => {
+                for ty in args.as_closure().upvar_tys() {
+                    self.walk(ty);
+                }
+            }
+            // TODO: trait objects (and generics) could drop anything, so we could handle them
+            // conservatively like we do for indirect calls.
+            _ => {}
+        }
+    }
+}
@@ -1,23 +1,55 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use clap::ValueEnum;
+use rustc_abi::ExternAbi;
 use rustc_hir::{
     def::DefKind,
     def_id::{DefId, LocalDefId},
 };
 use rustc_middle::ty::TyCtxt;
+use rustc_span::{Symbol, sym};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    ARGS, EntrySelector,
     properties::Property,
     reachability::attrs::{self, SniffToolAttr},
 };
 
-pub fn analysis_entry_points<P: Property>(tcx: TyCtxt, is_dependency: bool) -> Vec<LocalDefId> {
-    // TODO: should use a btree rather than a hash set here so that we'll have a consistent order
+/// Why a given function was chosen as an entry point for analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    /// Directly annotated with a `sniff_tool` attribute.
+    Annotated,
+    /// Inside a `mod`, `impl` or `trait` annotated with a `sniff_tool` attribute.
+    Module,
+    /// Every function in the crate, from a crate-wide annotation.
+    Global,
+    /// A public function, from a crate-wide `_pub` annotation or because we're analyzing a dependency.
+    Public,
+    /// An exported symbol (e.g. `#[no_mangle]` or `extern "C"`).
+    Exported,
+    /// A `#[test]` function.
+    Test,
+    /// The `main` function of a binary or example.
+    Main,
+    /// An implementation of a public trait, which could be called from outside the crate.
+    PublicTraitImpl,
+}
+
+pub fn analysis_entry_points<P: Property>(
+    tcx: TyCtxt,
+    is_dependency: bool,
+) -> Vec<(LocalDefId, EntryKind)> {
+    // TODO: should use a btree rather than a hash map here so that we'll have a consistent order
     // but local def ids aren't ord so this will likely require an upstream changes.
-    let mut entry_points = HashSet::new();
+    let mut entry_points = HashMap::new();
 
     if is_dependency {
-        return all_pub_local_fn_defs(tcx).collect::<Vec<_>>();
+        return all_pub_local_fn_defs(tcx)
+            .map(|def| (def, EntryKind::Public))
+            .collect::<Vec<_>>();
     }
 
     if let Some(global_annotation) = find_global_annotation::<P>(tcx) {
@@ -26,19 +58,153 @@ pub fn analysis_entry_points<P: Property>(tcx: TyCtxt, is_dependency: bool) -> V
             // so we have to continue looking for annotated local defs.
             let just_pub = all_pub_local_fn_defs(tcx).collect::<Vec<_>>();
             log::debug!("entry from global all_pub annotation is {just_pub:#?}");
-            entry_points.extend(just_pub);
+            extend_entry_points(&mut entry_points, just_pub, EntryKind::Public);
         } else {
             // This is everything we can possibly analyzing the local crate, so just return that.
-            return all_local_fn_defs(tcx).collect();
+            return all_local_fn_defs(tcx)
+                .map(|def| (def, EntryKind::Global))
+                .collect();
         }
     }
 
     let local = annotated_local_defs::<P>(tcx).collect::<Vec<_>>();
     log::debug!("entry from local annotations is {local:#?}");
-    entry_points.extend(local);
+    extend_entry_points(&mut entry_points, local, EntryKind::Annotated);
+
+    let in_modules = module_annotated_local_defs::<P>(tcx).collect::<Vec<_>>();
+    log::debug!("entry from module annotations is {in_modules:#?}");
+    extend_entry_points(&mut entry_points, in_modules, EntryKind::Module);
+
+    for selector in entry_selectors(tcx) {
+        let selected = selected_local_defs(tcx, selector);
+        log::debug!("entry from {selector:?} selector is {selected:#?}");
+        extend_entry_points(&mut entry_points, selected, selector.into());
+    }
+
+    // Reachability sorts the items it finds, but sort entry points too so they're deterministic
+    // on their own.
+    let mut entry_points = entry_points.into_iter().collect::<Vec<_>>();
+    entry_points.sort_by_cached_key(|(def, _)| tcx.def_path_str(def.to_def_id()));
+    entry_points
+}
+
+/// Add new entry points of a given `kind`, preferring the more specific kinds we've already found.
+fn extend_entry_points(
+    entry_points: &mut HashMap<LocalDefId, EntryKind>,
+    new: impl IntoIterator<Item = LocalDefId>,
+    kind: EntryKind,
+) {
+    for def in new {
+        entry_points.entry(def).or_insert(kind);
+    }
+}
+
+impl From<EntrySelector> for EntryKind {
+    fn from(selector: EntrySelector) -> Self {
+        match selector {
+            EntrySelector::Exported => Self::Exported,
+            EntrySelector::Tests => Self::Test,
+            EntrySelector::Main => Self::Main,
+            EntrySelector::TraitImpls => Self::PublicTraitImpl,
+        }
+    }
+}
+
+/// All the entry point selectors enabled either on the CLI or with a crate-wide
+/// `#![sniff_tool::entry_points(..)]` attribute.
+fn entry_selectors(tcx: TyCtxt) -> HashSet<EntrySelector> {
+    let from_args = ARGS.lock().unwrap().as_ref().unwrap().entry_points.clone();
+
+    let from_attrs = attrs::get_sniff_tool_attr_args(tcx.hir_krate_attrs(), "entry_points")
+        .into_iter()
+        .map(|(arg, span)| {
+            // Attribute args have to be identifiers, so accept snake case versions of the CLI names.
+            EntrySelector::from_str(&arg.replace('_', "-"), true).unwrap_or_else(|_| {
+                tcx.dcx()
+                    .struct_span_fatal(span, format!("unknown entry point selector `{arg}`"))
+                    .emit()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    from_args.into_iter().chain(from_attrs).collect()
+}
+
+fn selected_local_defs(tcx: TyCtxt, selector: EntrySelector) -> Vec<LocalDefId> {
+    match selector {
+        EntrySelector::Exported => all_local_fn_defs(tcx)
+            .filter(|def| is_exported(tcx, *def))
+            .collect(),
+        EntrySelector::Tests => test_fn_defs(tcx),
+        EntrySelector::Main => tcx
+            .entry_fn(())
+            .and_then(|(def_id, _)| def_id.as_local())
+            .into_iter()
+            .collect(),
+        EntrySelector::TraitImpls => all_local_fn_defs(tcx)
+            .filter(|def| is_public_trait_impl(tcx, *def))
+            .collect(),
+    }
+}
 
-    // Currently sort at the reachability level, so don't have to do it here.
-    entry_points.into_iter().collect()
+/// Whether a function can be called directly from outside of Rust.
+fn is_exported(tcx: TyCtxt, def: LocalDefId) -> bool {
+    if tcx.is_closure_like(def.to_def_id()) {
+        return false;
+    }
+
+    tcx.codegen_fn_attrs(def)
+        .contains_extern_indicator(tcx, def.to_def_id())
+        || tcx.fn_sig(def).skip_binder().abi() != ExternAbi::Rust
+}
+
+/// Finds all `#[test]` functions.
+///
+/// The test harness replaces the attribute with a `#[rustc_test_marker]` const of the same name
+/// as the test function, so we find those consts and then the function right next to them.
+fn test_fn_defs(tcx: TyCtxt) -> Vec<LocalDefId> {
+    let test_markers = test_markers(tcx);
+    all_local_fn_defs(tcx)
+        .filter(|def| is_marked_test(tcx, &test_markers, *def))
+        .collect()
+}
+
+/// Whether a function is a `#[test]` function.
+pub fn is_test_fn(tcx: TyCtxt, def: LocalDefId) -> bool {
+    is_marked_test(tcx, &test_markers(tcx), def)
+}
+
+/// The parent module and name of each `#[rustc_test_marker]` const.
+fn test_markers(tcx: TyCtxt) -> HashSet<(Option<DefId>, Symbol)> {
+    tcx.hir_body_owners()
+        .filter(|owner| matches!(tcx.def_kind(*owner), DefKind::Const))
+        .filter(|owner| {
+            tcx.get_all_attrs(owner.to_def_id())
+                .iter()
+                .any(|attr| attr.has_name(sym::rustc_test_marker))
+        })
+        .map(|marker| (tcx.opt_parent(marker.to_def_id()), tcx.item_name(marker)))
+        .collect()
+}
+
+fn is_marked_test(
+    tcx: TyCtxt,
+    test_markers: &HashSet<(Option<DefId>, Symbol)>,
+    def: LocalDefId,
+) -> bool {
+    matches!(tcx.def_kind(def), DefKind::Fn)
+        && test_markers.contains(&(tcx.opt_parent(def.to_def_id()), tcx.item_name(def)))
+}
+
+/// Whether a function is part of the implementation of a trait that code outside this crate can use.
+fn is_public_trait_impl(tcx: TyCtxt, def: LocalDefId) -> bool {
+    let Some(trait_def) = tcx
+        .trait_impl_of_assoc(def.to_def_id())
+        .and_then(|impl_def| tcx.trait_id_of_impl(impl_def))
+    else {
+        return false;
+    };
+    !trait_def.is_local() || tcx.visibility(trait_def).is_public()
 }
 
 fn find_global_annotation<P: Property>(tcx: TyCtxt) -> Option<GlobalAnnotation> {
@@ -78,7 +244,8 @@ fn is_def_analyzeable(tcx: TyCtxt) -> impl Fn(&LocalDefId) -> bool {
         // TODO: should we be handling more here?? Yes we should. Key exports of zerocopy
         // are Associated functions, so we should at least handle that...
         let res = match kind {
-            DefKind::Fn | DefKind::AssocFn => true,
+            // Closures also cover coroutines (e.g. `async` blocks and `async fn` bodies).
+            DefKind::Fn | DefKind::AssocFn | DefKind::Closure => true,
             // For context, zerocopy has all of these, but I don't think we want to analyze them...
             // Don't want anything to fall through the cracks though, so left as todo.
             // DefKind::Impl { .. } | DefKind::AssocConst => false,
@@ -95,6 +262,11 @@ fn is_def_analyzeable(tcx: TyCtxt) -> impl Fn(&LocalDefId) -> bool {
 
 fn all_pub_local_fn_defs(tcx: TyCtxt) -> impl Iterator<Item = LocalDefId> {
     all_local_fn_defs(tcx).filter(move |owner| {
+        // Closures are never public themselves, they're only reachable through their parent.
+        if tcx.is_closure_like(owner.to_def_id()) {
+            return false;
+        }
+
         let directly_public = tcx.visibility(*owner).is_public();
         let span = tcx.def_span(*owner);
 
@@ -108,6 +280,42 @@ fn annotated_local_defs<P: Property>(tcx: TyCtxt) -> impl Iterator<Item = LocalD
         .filter(move |item| is_directly_annotated::<P>(tcx, item.to_def_id()))
 }
 
+/// All functions within a `mod`, `impl` or `trait` that's annotated for analysis, including
+/// through any number of nested modules.
+fn module_annotated_local_defs<P: Property>(tcx: TyCtxt) -> impl Iterator<Item = LocalDefId> {
+    all_local_fn_defs(tcx).filter(move |def| {
+        // Closures are reachable through their parent, so don't need to be entry points themselves.
+        if tcx.is_closure_like(def.to_def_id()) {
+            return false;
+        }
+
+        let is_public = tcx.visibility(*def).is_public();
+        enclosing_scopes(tcx, def.to_def_id()).any(|scope| {
+            attrs::get_sniff_tool_attrs(
+                tcx.get_all_attrs(scope),
+                &SniffToolAttr::try_from_string_pub,
+            )
+            .into_iter()
+            .any(|(attr, just_check_pub)| {
+                SniffToolAttr::matches_property::<P>(attr) && (!just_check_pub || is_public)
+            })
+        })
+    })
+}
+
+/// The `mod`, `impl` and `trait` items that syntactically contain an item, innermost first.
+/// Stops before the crate root, as that's handled by [`find_global_annotation`].
+fn enclosing_scopes(tcx: TyCtxt, item: DefId) -> impl Iterator<Item = DefId> {
+    std::iter::successors(tcx.opt_parent(item), move |parent| tcx.opt_parent(*parent))
+        .filter(|parent| !parent.is_crate_root())
+        .filter(move |parent| {
+            matches!(
+                tcx.def_kind(*parent),
+                DefKind::Mod | DefKind::Impl { .. } | DefKind::Trait
+            )
+        })
+}
+
 /// Checks if a given item is directly annotated for analysis by a `sniff_tool` attribute.
 fn is_directly_annotated<P: Property>(tcx: TyCtxt, item: DefId) -> bool {
     attrs::attrs_for(item, tcx)
@@ -1,8 +1,9 @@
 pub mod attrs;
 mod calls;
+mod drops;
 mod entry;
 mod reach;
 
-pub use calls::{CallsWObligations, find_calls_w_obligations};
-pub use entry::analysis_entry_points;
-pub use reach::{LocallyReachable, locally_reachable_from};
+pub use calls::{CallKind, CallsWObligations, find_calls_w_obligations};
+pub use entry::{EntryKind, analysis_entry_points, is_test_fn};
+pub use reach::{LocallyReachable, is_unsafe_fn, locally_reachable_from};
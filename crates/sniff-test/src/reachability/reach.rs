@@ -2,10 +2,17 @@
 //! for each function call, does it satisfy the requirements?
 //!
 
+use crate::reachability::{CallKind, drops};
 use crate::rustc_middle::mir::visit::Visitor;
+use crate::{ARGS, IndirectCallsPosture};
+use rustc_abi::ExternAbi;
 use rustc_hir::def_id::{DefId, LOCAL_CRATE, LocalDefId};
-use rustc_middle::mir::{Operand, TerminatorKind};
-use rustc_middle::ty::{TyCtxt, TyKind};
+use rustc_hir::{BodyOwnerKind, Safety};
+use rustc_middle::mir::{AggregateKind, Body, CastKind, Operand, Rvalue, TerminatorKind};
+use rustc_middle::ty::adjustment::PointerCoercion;
+use rustc_middle::ty::{
+    FnSig, Instance, InstanceKind, Ty, TyCtxt, TyKind, TypeVisitableExt, TypingEnv,
+};
 use rustc_span::Span;
 use std::collections::{HashMap, VecDeque};
 
@@ -13,30 +20,38 @@ use std::collections::{HashMap, VecDeque};
 pub struct LocallyReachable {
     /// The item that can be reached.
     pub reach: LocalDefId,
-    /// The path of calls between items through which you can reach this item.
-    pub through: Vec<(LocalDefId, Span)>,
+    /// The path of calls (or drops) between items through which you can reach this item.
+    pub through: Vec<(LocalDefId, Span, CallKind)>,
     /// The functions (not necessarily local) that this one calls to.
     pub calls_to: HashMap<DefId, Vec<Span>>,
+    /// The `Drop::drop` implementations (not necessarily local) run by values this one drops.
+    pub drops: HashMap<DefId, Vec<Span>>,
 }
 
 impl LocallyReachable {
-    /// Say that this locally reachable item can go to another `def_id` through a call at a given `span`.
-    fn extended_to(&self, def_id: LocalDefId, span: Span) -> Self {
+    /// Say that this locally reachable item can go to another `def_id` through a call (or drop) at
+    /// a given `span`.
+    fn extended_to(&self, def_id: LocalDefId, span: Span, kind: CallKind) -> Self {
         LocallyReachable {
             reach: def_id,
             through: self
                 .through
                 .iter()
                 .copied()
-                .chain(std::iter::once((self.reach, span)))
+                .chain(std::iter::once((self.reach, span, kind)))
                 .collect(),
             calls_to: HashMap::new(),
+            drops: HashMap::new(),
         }
     }
 
     fn calls_to(&mut self, def_id: DefId, span: Span) {
         self.calls_to.entry(def_id).or_default().push(span);
     }
+
+    fn drops(&mut self, def_id: DefId, span: Span) {
+        self.drops.entry(def_id).or_default().push(span);
+    }
 }
 
 /// Get an iterator over all locally reachable function definitions from the given `entry_points`.
@@ -57,7 +72,7 @@ pub fn locally_reachable_from(
 
     let unsafe_reachable = all_reachable
         .iter()
-        .filter(|(def_id, _calls)| tcx.fn_sig(*def_id).skip_binder().safety().is_unsafe())
+        .filter(|(def_id, _calls)| is_unsafe_fn(tcx, **def_id))
         .map(|(a, b)| (a, b.len(), b))
         .collect::<Vec<_>>();
 
@@ -78,6 +93,11 @@ pub fn locally_reachable_from(
     locally_reachable
 }
 
+/// Whether `def_id` is an `unsafe fn`.
+pub fn is_unsafe_fn(tcx: TyCtxt, def_id: DefId) -> bool {
+    tcx.fn_sig(def_id).skip_binder().safety().is_unsafe()
+}
+
 struct CallGraphVisitor<'tcx> {
     tcx: TyCtxt<'tcx>,
     /// Queue of reachable items we want to visit.
@@ -86,10 +106,18 @@ struct CallGraphVisitor<'tcx> {
     to_visit: VecDeque<LocallyReachable>,
     locally_reachable: HashMap<LocalDefId, LocallyReachable>,
     all_reachable: HashMap<DefId, Vec<Span>>,
+    /// The local items that could be called indirectly. Only populated if we're conservatively
+    /// handling indirect calls.
+    indirect_targets: IndirectTargets<'tcx>,
 }
 
 impl<'tcx> CallGraphVisitor<'tcx> {
     fn new(tcx: TyCtxt<'tcx>, entry_points: impl Iterator<Item = LocalDefId>) -> Self {
+        let indirect_targets = match indirect_calls_posture() {
+            IndirectCallsPosture::Ignore => IndirectTargets::default(),
+            IndirectCallsPosture::Conservative => indirect_targets(tcx),
+        };
+
         Self {
             tcx,
             to_visit: entry_points
@@ -97,10 +125,12 @@ impl<'tcx> CallGraphVisitor<'tcx> {
                     reach,
                     through: Vec::new(),
                     calls_to: HashMap::new(),
+                    drops: HashMap::new(),
                 })
                 .collect(),
             locally_reachable: HashMap::new(),
             all_reachable: HashMap::new(),
+            indirect_targets,
         }
     }
 
@@ -124,12 +154,14 @@ impl<'tcx> CallGraphVisitor<'tcx> {
                 }
                 let body = self.tcx.optimized_mir(d.reach);
                 // log::debug!("SUCCESS");
-                let mut visitor = BodyVisitor(
-                    self.tcx,
-                    &mut self.to_visit,
-                    &mut d,
-                    &mut self.all_reachable,
-                );
+                let mut visitor = BodyVisitor {
+                    tcx: self.tcx,
+                    body,
+                    to_visit: &mut self.to_visit,
+                    current: &mut d,
+                    all_reachable: &mut self.all_reachable,
+                    indirect_targets: &self.indirect_targets,
+                };
                 visitor.visit_body(body);
                 self.locally_reachable.insert(d.reach, d);
             }
@@ -138,13 +170,15 @@ impl<'tcx> CallGraphVisitor<'tcx> {
     }
 }
 
-#[allow(dead_code)]
-struct BodyVisitor<'tcx, 'm>(
-    TyCtxt<'tcx>,
-    &'m mut VecDeque<LocallyReachable>,
-    &'m mut LocallyReachable,
-    &'m mut HashMap<DefId, Vec<Span>>,
-);
+struct BodyVisitor<'tcx, 'm> {
+    tcx: TyCtxt<'tcx>,
+    body: &'tcx Body<'tcx>,
+    to_visit: &'m mut VecDeque<LocallyReachable>,
+    /// The item whose body we're visiting.
+    current: &'m mut LocallyReachable,
+    all_reachable: &'m mut HashMap<DefId, Vec<Span>>,
+    indirect_targets: &'m IndirectTargets<'tcx>,
+}
 
 impl<'tcx> rustc_middle::mir::visit::Visitor<'tcx> for BodyVisitor<'tcx, '_> {
     fn visit_terminator(
@@ -152,25 +186,312 @@ impl<'tcx> rustc_middle::mir::visit::Visitor<'tcx> for BodyVisitor<'tcx, '_> {
         terminator: &rustc_middle::mir::Terminator<'tcx>,
         location: rustc_middle::mir::Location,
     ) {
-        if let TerminatorKind::Call { func, .. } = &terminator.kind
-            && let Operand::Constant(box co) = func
-            && let TyKind::FnDef(def_id, _substs) = co.const_.ty().kind()
+        if let TerminatorKind::Call { func, .. } = &terminator.kind {
+            let span = terminator.source_info.span;
+            if let Operand::Constant(box co) = func
+                && let TyKind::FnDef(def_id, substs) = co.const_.ty().kind()
+            {
+                self.current.calls_to(*def_id, span);
+                // TODO: here need to handle non-local reachable
+                if let Some(local_def) = def_id.as_local() {
+                    // Doing BFS here to ensure we get the shortest path possible to all reachable items.
+                    self.to_visit.push_back(self.current.extended_to(
+                        local_def,
+                        span,
+                        CallKind::Call,
+                    ));
+                } else {
+                    // non-local crate
+                    self.all_reachable.entry(*def_id).or_default().push(span);
+                }
+
+                if indirect_calls_posture() == IndirectCallsPosture::Conservative {
+                    for local_def in self.possible_impls(*def_id, substs) {
+                        self.to_visit.push_back(self.current.extended_to(
+                            local_def,
+                            span,
+                            CallKind::Call,
+                        ));
+                    }
+                }
+            } else if indirect_calls_posture() == IndirectCallsPosture::Conservative
+                && let TyKind::FnPtr(..) = func.ty(self.body, self.tcx).kind()
+            {
+                let called_sig = self.tcx.instantiate_bound_regions_with_erased(
+                    func.ty(self.body, self.tcx).fn_sig(self.tcx),
+                );
+                for (local_def, _) in self
+                    .indirect_targets
+                    .address_taken
+                    .iter()
+                    .filter(|(_, sig)| sigs_could_match(self.tcx, called_sig, *sig))
+                {
+                    self.to_visit.push_back(self.current.extended_to(
+                        *local_def,
+                        span,
+                        CallKind::Call,
+                    ));
+                }
+            }
+        }
+
+        // Unwinding drops the same values as the normal path, so just look at the latter.
+        if let TerminatorKind::Drop { place, .. } = &terminator.kind
+            && !self.body[location.block].is_cleanup
         {
-            self.2.calls_to(*def_id, terminator.source_info.span);
-            // TODO: here need to handle non-local reachable
-            if let Some(local_def) = def_id.as_local() {
-                // Doing BFS here to ensure we get the shortest path possible to all reachable items.
-                self.1
-                    .push_back(self.2.extended_to(local_def, terminator.source_info.span));
-            } else {
-                // non-local crate
-                self.3
-                    .entry(*def_id)
-                    .or_default()
-                    .push(terminator.source_info.span);
+            let span = terminator.source_info.span;
+            let ty = place.ty(self.body, self.tcx).ty;
+            let typing_env = TypingEnv::post_analysis(self.tcx, self.current.reach);
+            for destructor in drops::destructors_run_by(self.tcx, typing_env, ty) {
+                self.current.drops(destructor, span);
+                if let Some(local_def) = destructor.as_local() {
+                    self.to_visit.push_back(self.current.extended_to(
+                        local_def,
+                        span,
+                        CallKind::Drop,
+                    ));
+                } else {
+                    self.all_reachable.entry(destructor).or_default().push(span);
+                }
             }
         }
 
         self.super_terminator(terminator, location);
     }
+
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: rustc_middle::mir::Location) {
+        // Closures and coroutines have their own bodies, so any that are constructed here are
+        // reachable as their own items.
+        if let Rvalue::Aggregate(box kind, _operands) = rvalue
+            && let AggregateKind::Closure(def_id, _)
+            | AggregateKind::Coroutine(def_id, _)
+            | AggregateKind::CoroutineClosure(def_id, _) = kind
+            && let Some(local_def) = def_id.as_local()
+        {
+            let span = self.body.source_info(location).span;
+            self.to_visit
+                .push_back(self.current.extended_to(local_def, span, CallKind::Call));
+        }
+
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_const_operand(
+        &mut self,
+        constant: &rustc_middle::mir::ConstOperand<'tcx>,
+        location: rustc_middle::mir::Location,
+    ) {
+        // Closures that don't capture anything are zero-sized, so they can get optimized into
+        // constants rather than being constructed as an aggregate.
+        if let TyKind::Closure(def_id, _)
+        | TyKind::Coroutine(def_id, _)
+        | TyKind::CoroutineClosure(def_id, _) = constant.const_.ty().kind()
+            && let Some(local_def) = def_id.as_local()
+        {
+            let span = self.body.source_info(location).span;
+            self.to_visit
+                .push_back(self.current.extended_to(local_def, span, CallKind::Call));
+        }
+
+        self.super_const_operand(constant, location);
+    }
+}
+
+impl<'tcx> BodyVisitor<'tcx, '_> {
+    /// The local items a call to trait method `def_id` could dispatch to, if it can't be resolved
+    /// to a single implementation statically.
+    fn possible_impls(
+        &self,
+        def_id: DefId,
+        substs: rustc_middle::ty::GenericArgsRef<'tcx>,
+    ) -> Vec<LocalDefId> {
+        let Some(trait_def) = self.tcx.trait_of_assoc(def_id) else {
+            return Vec::new();
+        };
+
+        let typing_env = TypingEnv::post_analysis(self.tcx, self.current.reach);
+        // If we can resolve to a single (non-virtual) implementation, just go there.
+        if let Ok(Some(Instance {
+            def: InstanceKind::Item(resolved),
+            ..
+        })) = Instance::try_resolve(self.tcx, typing_env, def_id, substs)
+        {
+            // Non-local `Fn*` impls (e.g. for `Box<dyn Fn>`) can forward the call on to a closure.
+            return match resolved.as_local() {
+                Some(local_def) => vec![local_def],
+                None => self.closures_called_through(trait_def, substs),
+            };
+        }
+
+        // Otherwise, it could be any implementation, either because we're calling through a
+        // `dyn Trait` or because the `Self` type is still generic.
+        let tcx = self.tcx;
+        let impls = tcx
+            .all_impls(trait_def)
+            .filter_map(move |impl_def| {
+                tcx.impl_item_implementor_ids(impl_def)
+                    .get(&def_id)
+                    .copied()
+            })
+            .filter_map(DefId::as_local);
+        let default_body = def_id
+            .as_local()
+            .filter(|_| tcx.defaultness(def_id).has_value());
+        impls
+            .chain(default_body)
+            .chain(self.closures_called_through(trait_def, substs))
+            .collect()
+    }
+
+    /// The local closures a call to a method of `trait_def` could dispatch to. Closures don't have
+    /// impls of the `Fn*` traits we can look up, so any of them with a matching signature could be
+    /// what's called.
+    fn closures_called_through(
+        &self,
+        trait_def: DefId,
+        substs: rustc_middle::ty::GenericArgsRef<'tcx>,
+    ) -> Vec<LocalDefId> {
+        let Some(called_sig) = self.fn_trait_call_sig(trait_def, substs) else {
+            return Vec::new();
+        };
+        self.indirect_targets
+            .closures
+            .iter()
+            .filter(|(_, sig)| sigs_could_match(self.tcx, called_sig, *sig))
+            .map(|(closure, _)| *closure)
+            .collect()
+    }
+
+    /// The (tupled) signature being called through a method of `trait_def`, if it's one of the
+    /// `Fn*` traits.
+    fn fn_trait_call_sig(
+        &self,
+        trait_def: DefId,
+        substs: rustc_middle::ty::GenericArgsRef<'tcx>,
+    ) -> Option<FnSig<'tcx>> {
+        self.tcx.fn_trait_kind_from_def_id(trait_def)?;
+        let output_def = self.tcx.lang_items().fn_once_output()?;
+        let (self_ty, args_ty) = (substs.type_at(0), substs.type_at(1));
+
+        // Normalizing the output fails if `Self` is generic, but then the projection is generic
+        // too, so it'll match any closure's output.
+        let output = Ty::new_projection(self.tcx, output_def, [self_ty, args_ty]);
+        let typing_env = TypingEnv::post_analysis(self.tcx, self.current.reach);
+        let output = self
+            .tcx
+            .try_normalize_erasing_regions(typing_env, output)
+            .unwrap_or(output);
+
+        Some(
+            self.tcx
+                .mk_fn_sig([args_ty], output, false, Safety::Safe, ExternAbi::RustCall),
+        )
+    }
+}
+
+fn indirect_calls_posture() -> IndirectCallsPosture {
+    ARGS.lock().unwrap().as_ref().unwrap().indirect_calls
+}
+
+/// The local items that could be called indirectly, along with the signature they'd be called at.
+#[derive(Debug, Default)]
+struct IndirectTargets<'tcx> {
+    /// Local functions that have been coerced to `fn` pointers.
+    address_taken: Vec<(LocalDefId, FnSig<'tcx>)>,
+    /// Local closures that have been constructed, with their tupled signatures, as they could be
+    /// called through a `dyn Fn*` (or generic `F: Fn*`).
+    closures: Vec<(LocalDefId, FnSig<'tcx>)>,
+}
+
+/// Finds all local functions that are coerced to a `fn` pointer, and all local closures that are
+/// constructed, somewhere in the local crate, including within the initializers of `const`s and
+/// `static`s (e.g. for manual vtables).
+fn indirect_targets(tcx: TyCtxt<'_>) -> IndirectTargets<'_> {
+    let mut finder = AddressTakenFinder {
+        tcx,
+        found: IndirectTargets::default(),
+    };
+
+    for owner in tcx.hir_body_owners() {
+        let body = match tcx.hir_body_owner_kind(owner) {
+            BodyOwnerKind::Fn | BodyOwnerKind::Closure => tcx.optimized_mir(owner),
+            BodyOwnerKind::Const { .. } | BodyOwnerKind::Static(_) => tcx.mir_for_ctfe(owner),
+            BodyOwnerKind::GlobalAsm => continue,
+        };
+        finder.visit_body(body);
+    }
+
+    log::debug!("indirect call targets are {:#?}", finder.found);
+    finder.found
+}
+
+struct AddressTakenFinder<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    found: IndirectTargets<'tcx>,
+}
+
+impl<'tcx> AddressTakenFinder<'tcx> {
+    fn closure_constructed(
+        &mut self,
+        def_id: DefId,
+        substs: rustc_middle::ty::GenericArgsRef<'tcx>,
+    ) {
+        if let Some(local_def) = def_id.as_local() {
+            let sig = self
+                .tcx
+                .instantiate_bound_regions_with_erased(substs.as_closure().sig());
+            self.found.closures.push((local_def, sig));
+        }
+    }
+}
+
+impl<'tcx> rustc_middle::mir::visit::Visitor<'tcx> for AddressTakenFinder<'tcx> {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: rustc_middle::mir::Location) {
+        if let Rvalue::Cast(CastKind::PointerCoercion(PointerCoercion::ReifyFnPointer, _), op, _) =
+            rvalue
+            && let Operand::Constant(box co) = op
+            && let TyKind::FnDef(def_id, substs) = co.const_.ty().kind()
+            && let Some(local_def) = def_id.as_local()
+        {
+            let sig = self.tcx.instantiate_bound_regions_with_erased(
+                self.tcx.fn_sig(def_id).instantiate(self.tcx, substs),
+            );
+            self.found.address_taken.push((local_def, sig));
+        }
+
+        if let Rvalue::Aggregate(box AggregateKind::Closure(def_id, substs), _) = rvalue {
+            self.closure_constructed(*def_id, substs);
+        }
+
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_const_operand(
+        &mut self,
+        constant: &rustc_middle::mir::ConstOperand<'tcx>,
+        location: rustc_middle::mir::Location,
+    ) {
+        // Closures that don't capture anything can be constants rather than aggregates.
+        if let TyKind::Closure(def_id, substs) = constant.const_.ty().kind() {
+            self.closure_constructed(*def_id, substs);
+        }
+
+        self.super_const_operand(constant, location);
+    }
+}
+
+/// Whether a function with signature `candidate` could be what's called through a `fn` pointer with
+/// signature `called`. Generic types are assumed to match anything.
+fn sigs_could_match<'tcx>(tcx: TyCtxt<'tcx>, called: FnSig<'tcx>, candidate: FnSig<'tcx>) -> bool {
+    let types_match = |a: Ty<'tcx>, b: Ty<'tcx>| {
+        a.has_param() || b.has_param() || tcx.erase_regions(a) == tcx.erase_regions(b)
+    };
+
+    called.inputs().len() == candidate.inputs().len()
+        && called.c_variadic == candidate.c_variadic
+        && called
+            .inputs_and_output
+            .iter()
+            .zip(candidate.inputs_and_output)
+            .all(|(a, b)| types_match(a, b))
 }
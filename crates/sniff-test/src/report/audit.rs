@@ -0,0 +1,269 @@
+//! A Markdown inventory of every function with obligations in a crate, along with everything
+//! that relies on them, for security reviewers to audit.
+
+use std::{
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::{
+    annotations::PropertyViolation,
+    check::{CheckResults, CheckedFn},
+    properties::{self, Property},
+    report::{SourceLocation, write_report},
+};
+
+/// Write an audit inventory of the `results` for the current crate into `dir`, as something
+/// like `my_crate-lib.SAFETY.md`.
+pub fn write<'tcx, P: Property>(
+    tcx: TyCtxt<'tcx>,
+    dir: &Path,
+    property: P,
+    results: &CheckResults<'tcx, P>,
+) {
+    // Links are relative to wherever the document ends up.
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let links_from = if dir == Path::new("-") {
+        cwd.clone()
+    } else {
+        dir.to_path_buf()
+    };
+    let audit = Audit {
+        tcx,
+        property,
+        results,
+        cwd,
+        links_from,
+    };
+
+    let extension = format!("{}.md", property.fn_def_section().to_uppercase());
+    write_report(tcx, dir, &extension, |out| audit.render(out));
+}
+
+struct Audit<'a, 'tcx, P: Property> {
+    tcx: TyCtxt<'tcx>,
+    property: P,
+    results: &'a CheckResults<'tcx, P>,
+    /// Where rustc was run from, which source paths are relative to.
+    cwd: PathBuf,
+    links_from: PathBuf,
+}
+
+/// A call to a function with obligations.
+struct CallSite<'a> {
+    caller: &'a CheckedFn,
+    span: Span,
+}
+
+impl<P: Property> Audit<'_, '_, P> {
+    fn render(&self, out: &mut dyn Write) -> anyhow::Result<()> {
+        let tcx = self.tcx;
+        let section = self.property.fn_def_section();
+        let with_obligations = self
+            .results
+            .functions
+            .iter()
+            .filter(|checked| has_obligations(checked))
+            .collect::<Vec<_>>();
+
+        writeln!(
+            out,
+            "# `{section}` audit for `{}`\n",
+            tcx.crate_name(LOCAL_CRATE)
+        )?;
+        writeln!(
+            out,
+            "Generated by sniff-test from the {} functions reachable from {} entry points, \
+             {} of which have obligations.\n",
+            self.results.functions.len(),
+            self.results.entry_points.len(),
+            with_obligations.len(),
+        )?;
+
+        writeln!(out, "## Functions with obligations\n")?;
+        if with_obligations.is_empty() {
+            writeln!(out, "None.\n")?;
+        }
+        for checked in with_obligations {
+            self.render_fn(out, checked)?;
+        }
+
+        writeln!(out, "## Axioms outside functions with obligations\n")?;
+        let axiom_rows = self
+            .results
+            .functions
+            .iter()
+            .filter(|checked| !has_obligations(checked))
+            .flat_map(|checked| {
+                properties::find_axioms(tcx, &checked.func, self.property).map(move |axiom| {
+                    format!(
+                        "| {} | {} | {} | {} |",
+                        self.fn_name(checked.func.reach.to_def_id()),
+                        axiom.axiom,
+                        self.link(axiom.span),
+                        justification_cell(checked, axiom.span),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        if axiom_rows.is_empty() {
+            writeln!(out, "None.")?;
+        } else {
+            writeln!(out, "| Function | Axiom | Location | Justification |")?;
+            writeln!(out, "| --- | --- | --- | --- |")?;
+            for row in axiom_rows {
+                writeln!(out, "{row}")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_fn(&self, out: &mut dyn Write, checked: &CheckedFn) -> anyhow::Result<()> {
+        let tcx = self.tcx;
+        let def_id = checked.func.reach.to_def_id();
+        writeln!(out, "### {}\n", self.fn_name(def_id))?;
+        writeln!(out, "Defined at {}.\n", self.link(tcx.def_span(def_id)))?;
+
+        let annotation = checked
+            .annotation
+            .as_ref()
+            .expect("functions with obligations are annotated");
+        match &annotation.local_violation_annotation {
+            PropertyViolation::Conditionally(conditions) if !conditions.is_empty() => {
+                writeln!(out, "Conditions:\n")?;
+                for condition in conditions {
+                    writeln!(
+                        out,
+                        "- `{}`: {}",
+                        condition.node.name,
+                        one_line(&condition.node.description)
+                    )?;
+                }
+            }
+            _ => writeln!(out, "Annotation:\n\n> {}", one_line(&annotation.text))?,
+        }
+        writeln!(out)?;
+
+        let axioms = properties::find_axioms(tcx, &checked.func, self.property).collect::<Vec<_>>();
+        if !axioms.is_empty() {
+            writeln!(out, "Axioms, covered by its own annotation:\n")?;
+            for axiom in axioms {
+                writeln!(out, "- {} at {}", axiom.axiom, self.link(axiom.span))?;
+            }
+            writeln!(out)?;
+        }
+
+        let call_sites = self.call_sites(def_id);
+        let mut trusted_by = Vec::new();
+        for call_site in &call_sites {
+            let caller = self.fn_name(call_site.caller.func.reach.to_def_id());
+            if !trusted_by.contains(&caller) {
+                trusted_by.push(caller);
+            }
+        }
+        if trusted_by.is_empty() {
+            writeln!(out, "Trusted by no reachable functions.\n")?;
+            return Ok(());
+        }
+        writeln!(out, "Trusted by {}.\n", trusted_by.join(", "))?;
+
+        writeln!(out, "| Caller | Location | Justification |")?;
+        writeln!(out, "| --- | --- | --- |")?;
+        for call_site in call_sites {
+            let caller = call_site.caller;
+            let justification = if has_obligations(caller) {
+                format!(
+                    "*covered by {}'s own annotation*",
+                    self.fn_name(caller.func.reach.to_def_id())
+                )
+            } else {
+                justification_cell(caller, call_site.span)
+            };
+            writeln!(
+                out,
+                "| {} | {} | {justification} |",
+                self.fn_name(caller.func.reach.to_def_id()),
+                self.link(call_site.span),
+            )?;
+        }
+        writeln!(out)?;
+
+        Ok(())
+    }
+
+    /// Every call (or drop) that runs `callee` from a reachable function.
+    fn call_sites(&self, callee: DefId) -> Vec<CallSite<'_>> {
+        self.results
+            .functions
+            .iter()
+            .flat_map(|caller| {
+                let calls = caller.func.calls_to.get(&callee).into_iter();
+                let drops = caller.func.drops.get(&callee).into_iter();
+                calls.chain(drops).flatten().map(move |span| CallSite {
+                    caller,
+                    span: *span,
+                })
+            })
+            .collect()
+    }
+
+    fn fn_name(&self, def_id: DefId) -> String {
+        format!("`{}`", self.tcx.def_path_str(def_id))
+    }
+
+    /// A Markdown link to the line of `span`.
+    fn link(&self, span: Span) -> String {
+        let location = SourceLocation::new(self.tcx, span);
+        let target = relative_path(&self.cwd.join(&location.file), &self.links_from);
+        format!(
+            "[{}:{}]({}#L{})",
+            location.file,
+            location.start_line,
+            target.display(),
+            location.start_line
+        )
+    }
+}
+
+pub fn has_obligations(checked: &CheckedFn) -> bool {
+    checked
+        .annotation
+        .as_ref()
+        .is_some_and(|annotation| annotation.creates_obligation().is_some())
+}
+
+/// The justification for the axiom or call at `span` within `checked`, as a table cell.
+pub fn justification_cell(checked: &CheckedFn, span: Span) -> String {
+    checked
+        .justifications
+        .iter()
+        .find(|justification| justification.span == span)
+        .map_or_else(
+            || "**unjustified**".to_owned(),
+            |justification| one_line(&justification.text).replace('|', "\\|"),
+        )
+}
+
+/// Put multi-line text on a single line, so it fits in a list item or table cell.
+pub fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The path to `path` from the directory `base`, assuming both are absolute.
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path = path.components().collect::<Vec<_>>();
+    let base = base.components().collect::<Vec<_>>();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    base[common..]
+        .iter()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .map(|_| Component::ParentDir)
+        .chain(path[common..].iter().copied())
+        .collect()
+}
@@ -0,0 +1,267 @@
+//! Dumps of the call graph we walked to find reachable functions, for debugging why something
+//! is (or isn't) reachable and for feeding into other tools.
+
+use std::{collections::HashMap, io::Write, path::Path};
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_middle::ty::TyCtxt;
+use serde::Serialize;
+
+use crate::{
+    CallGraphFormat,
+    annotations::{self, DefAnnotation},
+    check::{CheckResults, CheckedFn},
+    properties::Property,
+    reachability::{CallKind, EntryKind},
+    report::{SourceLocation, json::AnnotationReport, write_report},
+};
+
+/// Write the call graph from `results` in the given `format` into `dir`.
+pub fn write<P: Property>(
+    tcx: TyCtxt,
+    dir: &Path,
+    format: CallGraphFormat,
+    property: P,
+    results: &CheckResults<'_, P>,
+) {
+    let graph = CallGraph::new(tcx, property, results);
+    match format {
+        CallGraphFormat::Dot => write_report(tcx, dir, "callgraph.dot", |out| graph.write_dot(out)),
+        CallGraphFormat::Json => write_report(tcx, dir, "callgraph.json", |out| {
+            serde_json::to_writer_pretty(&mut *out, &graph)?;
+            writeln!(out)?;
+            Ok(())
+        }),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CallGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Debug, Serialize)]
+struct Node {
+    id: usize,
+    function: String,
+    local: bool,
+    /// Whether this is reachable from an entry point, which is always the case for external
+    /// functions as we only include those that are called.
+    reachable: bool,
+    entry_kind: Option<EntryKind>,
+    location: Option<SourceLocation>,
+    /// The chain of functions from an entry point through which this one is reachable.
+    reachable_through: Vec<String>,
+    /// The annotation creating obligations for this function's callers, if it has one.
+    obligations: Option<AnnotationReport>,
+}
+
+impl Node {
+    /// A local function we reached from an entry point.
+    fn reachable<P: Property>(
+        tcx: TyCtxt,
+        results: &CheckResults<'_, P>,
+        checked: &CheckedFn,
+    ) -> Self {
+        let func = checked.func.reach;
+        Node {
+            id: 0,
+            function: tcx.def_path_str(func.to_def_id()),
+            local: true,
+            reachable: true,
+            entry_kind: results
+                .entry_points
+                .iter()
+                .find(|(entry, _)| *entry == func)
+                .map(|(_, kind)| *kind),
+            location: Some(SourceLocation::new(tcx, tcx.def_span(func))),
+            reachable_through: checked
+                .func
+                .through
+                .iter()
+                .map(|(def, ..)| tcx.def_path_str(def.to_def_id()))
+                .collect(),
+            obligations: checked.annotation.as_ref().and_then(obligations),
+        }
+    }
+
+    /// A function we didn't check, either because it's external or because it wasn't reached.
+    fn other<P: Property>(
+        tcx: TyCtxt,
+        property: P,
+        results: &CheckResults<'_, P>,
+        function: String,
+        def_id: DefId,
+        reachable: bool,
+    ) -> Self {
+        let annotation =
+            annotations::parse_fn_def(tcx, &results.toml_annotations, def_id, property);
+        Node {
+            id: 0,
+            function,
+            local: def_id.is_local(),
+            reachable,
+            entry_kind: None,
+            location: def_id
+                .as_local()
+                .map(|local| SourceLocation::new(tcx, tcx.def_span(local))),
+            reachable_through: Vec::new(),
+            obligations: annotation.as_ref().and_then(obligations),
+        }
+    }
+}
+
+/// The annotation to report for a function, if it creates obligations for its callers.
+fn obligations(annotation: &DefAnnotation) -> Option<AnnotationReport> {
+    annotation
+        .creates_obligation()
+        .map(|_| AnnotationReport::from(annotation))
+}
+
+#[derive(Debug, Serialize)]
+struct Edge {
+    from: usize,
+    to: usize,
+    kind: CallKind,
+    call_sites: Vec<SourceLocation>,
+}
+
+impl CallGraph {
+    fn new<P: Property>(tcx: TyCtxt, property: P, results: &CheckResults<'_, P>) -> Self {
+        let mut nodes = results
+            .functions
+            .iter()
+            .map(|checked| Node::reachable(tcx, results, checked))
+            .collect::<Vec<_>>();
+        let mut ids = results
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(id, checked)| (checked.func.reach.to_def_id(), id))
+            .collect::<HashMap<_, _>>();
+
+        // Include local functions we never reached too, so it's clear they weren't.
+        let mut unreachable = tcx
+            .hir_body_owners()
+            .filter(|def| is_fn_like(tcx, *def) && !ids.contains_key(&def.to_def_id()))
+            .map(|def| (tcx.def_path_str(def.to_def_id()), def.to_def_id()))
+            .collect::<Vec<_>>();
+        unreachable.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut external = results
+            .functions
+            .iter()
+            .flat_map(|checked| {
+                checked
+                    .func
+                    .calls_to
+                    .keys()
+                    .chain(checked.func.drops.keys())
+            })
+            .map(|callee| (tcx.def_path_str(*callee), *callee))
+            .collect::<Vec<_>>();
+        external.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let unreachable = unreachable.into_iter().map(|def| (def, false));
+        let external = external.into_iter().map(|def| (def, true));
+        for ((function, def_id), reachable) in unreachable.chain(external) {
+            if ids.contains_key(&def_id) {
+                continue;
+            }
+            ids.insert(def_id, nodes.len());
+            nodes.push(Node::other(
+                tcx, property, results, function, def_id, reachable,
+            ));
+        }
+        for (id, node) in nodes.iter_mut().enumerate() {
+            node.id = id;
+        }
+
+        let mut edges = Vec::new();
+        for (from, checked) in results.functions.iter().enumerate() {
+            let calls = checked
+                .func
+                .calls_to
+                .iter()
+                .map(|(callee, spans)| (CallKind::Call, callee, spans));
+            let drops = checked
+                .func
+                .drops
+                .iter()
+                .map(|(callee, spans)| (CallKind::Drop, callee, spans));
+            for (kind, callee, spans) in calls.chain(drops) {
+                edges.push(Edge {
+                    from,
+                    to: ids[callee],
+                    kind,
+                    // Point at the call in this crate, even if it's within a macro.
+                    call_sites: spans
+                        .iter()
+                        .map(|span| SourceLocation::new(tcx, span.source_callsite()))
+                        .collect(),
+                });
+            }
+        }
+        // Calls are stored in hash maps, so sort to keep the output deterministic.
+        edges.sort_by_key(|edge| (edge.from, edge.to, edge.kind == CallKind::Drop));
+
+        CallGraph { nodes, edges }
+    }
+
+    fn write_dot(&self, out: &mut dyn Write) -> anyhow::Result<()> {
+        writeln!(out, "digraph callgraph {{")?;
+        writeln!(out, "    node [shape=box, fontname=monospace];")?;
+        for node in &self.nodes {
+            let mut attrs = vec![format!("label={}", dot_string(&node.function))];
+            if node.obligations.is_some() {
+                attrs.push("style=filled".to_owned());
+                attrs.push("fillcolor=lightblue".to_owned());
+            }
+            if node.entry_kind.is_some() {
+                attrs.push("penwidth=3".to_owned());
+            }
+            if !node.local {
+                attrs.push("shape=ellipse".to_owned());
+            }
+            if !node.reachable {
+                attrs.push("color=gray".to_owned());
+                attrs.push("fontcolor=gray".to_owned());
+            }
+            writeln!(out, "    n{} [{}];", node.id, attrs.join(", "))?;
+        }
+        for edge in &self.edges {
+            let lines = edge
+                .call_sites
+                .iter()
+                .map(|site| format!("L{}", site.start_line))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let style = match edge.kind {
+                CallKind::Call => "",
+                CallKind::Drop => ", style=dashed",
+            };
+            writeln!(
+                out,
+                "    n{} -> n{} [label={}{style}];",
+                edge.from,
+                edge.to,
+                dot_string(&lines)
+            )?;
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+}
+
+fn is_fn_like(tcx: TyCtxt, def: LocalDefId) -> bool {
+    matches!(
+        tcx.def_kind(def),
+        DefKind::Fn | DefKind::AssocFn | DefKind::Closure
+    )
+}
+
+/// Quote a string for use as a DOT identifier.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
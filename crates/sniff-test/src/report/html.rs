@@ -0,0 +1,276 @@
+//! A self-contained HTML report with an interactive call graph of the functions we checked,
+//! to help people find their way around a crate's uses of a property.
+//!
+//! Everything (including the script that draws the graph) is inlined into the one file, so it
+//! can be opened offline or attached to a CI run.
+
+use std::{borrow::Cow, path::Path};
+
+use rustc_hir::def_id::{DefId, LOCAL_CRATE, LocalDefId};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use serde::Serialize;
+
+use crate::{
+    check::{CheckResults, CheckedFn, LocalError, err},
+    properties::Property,
+    reachability::{CallKind, EntryKind},
+    report::{SourceLocation, write_report},
+};
+
+const TEMPLATE: &str = include_str!("report.html");
+/// Where the report's data goes in the template.
+const DATA_PLACEHOLDER: &str = "/* SNIFF_TEST_DATA */";
+/// The most lines of source to include for each function.
+const MAX_SNIPPET_LINES: usize = 200;
+
+/// Write an HTML report of the `results` for the current crate into `dir`.
+pub fn write<P: Property>(tcx: TyCtxt, dir: &Path, results: &CheckResults<'_, P>) {
+    let report = GraphReport::new(tcx, results);
+    write_report(tcx, dir, "html", |out| {
+        // Make sure nothing in the data (e.g. a snippet) can close the script tag it's in.
+        let data = serde_json::to_string_pretty(&report)?.replace("</", "<\\/");
+        write!(out, "{}", TEMPLATE.replace(DATA_PLACEHOLDER, &data))?;
+        Ok(())
+    });
+}
+
+#[derive(Debug, Serialize)]
+struct GraphReport {
+    #[serde(rename = "crate")]
+    krate: String,
+    property: &'static str,
+    passed: bool,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+/// How a function stands with respect to the property, which decides its colour.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    /// Has errors.
+    Unjustified,
+    /// Annotated as having obligations, so its callers have to consider them.
+    Obligations,
+    /// Justifies everything it uses.
+    Justified,
+    /// Doesn't use anything that needs justifying.
+    Clean,
+}
+
+#[derive(Debug, Serialize)]
+struct Node {
+    name: String,
+    location: SourceLocation,
+    /// How many calls it is from an entry point.
+    depth: usize,
+    entry_kind: Option<EntryKind>,
+    status: Status,
+    annotation: Option<String>,
+    findings: Vec<Finding>,
+    justifications: Vec<Finding>,
+    /// Calls to functions outside of this crate.
+    external_calls: Vec<ExternalCall>,
+    snippet: Snippet,
+}
+
+#[derive(Debug, Serialize)]
+struct Finding {
+    message: String,
+    lines: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExternalCall {
+    name: String,
+    kind: CallKind,
+    lines: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct Snippet {
+    start_line: usize,
+    lines: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Edge {
+    from: usize,
+    to: usize,
+    kind: CallKind,
+    lines: Vec<usize>,
+}
+
+impl GraphReport {
+    fn new<P: Property>(tcx: TyCtxt, results: &CheckResults<'_, P>) -> Self {
+        let index_of = |def_id: DefId| {
+            results
+                .functions
+                .iter()
+                .position(|checked| checked.func.reach.to_def_id() == def_id)
+        };
+
+        let mut edges = Vec::new();
+        let nodes = results
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(from, checked)| {
+                let mut external_calls = Vec::new();
+                for (kind, callee, spans) in callees(checked) {
+                    let lines = spans.iter().map(|span| line_of(tcx, *span)).collect();
+                    match index_of(*callee) {
+                        Some(to) => edges.push(Edge {
+                            from,
+                            to,
+                            kind,
+                            lines,
+                        }),
+                        None if !callee.is_local() => external_calls.push(ExternalCall {
+                            name: tcx.def_path_str(*callee),
+                            kind,
+                            lines,
+                        }),
+                        None => {}
+                    }
+                }
+                external_calls.sort_by(|a, b| a.name.cmp(&b.name));
+                Node::new(tcx, results, checked, external_calls)
+            })
+            .collect();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+
+        GraphReport {
+            krate: tcx.crate_name(LOCAL_CRATE).to_string(),
+            property: P::property_name(),
+            passed: results.passed(),
+            nodes,
+            edges,
+        }
+    }
+}
+
+impl Node {
+    fn new<P: Property>(
+        tcx: TyCtxt,
+        results: &CheckResults<'_, P>,
+        checked: &CheckedFn,
+        external_calls: Vec<ExternalCall>,
+    ) -> Self {
+        let func = checked.func.reach;
+        let findings = results
+            .errors
+            .iter()
+            .filter(|error| error.func().reach == func)
+            .map(|error| Finding {
+                message: err::message(tcx, error),
+                lines: error_lines(tcx, error),
+            })
+            .collect::<Vec<_>>();
+        let has_obligations = checked
+            .annotation
+            .as_ref()
+            .is_some_and(|annotation| annotation.creates_obligation().is_some());
+        let status = if !findings.is_empty() {
+            Status::Unjustified
+        } else if has_obligations {
+            Status::Obligations
+        } else if !checked.justifications.is_empty() {
+            Status::Justified
+        } else {
+            Status::Clean
+        };
+
+        Node {
+            name: tcx.def_path_str(func.to_def_id()),
+            location: SourceLocation::new(tcx, tcx.def_span(func)),
+            depth: checked.func.through.len(),
+            entry_kind: results
+                .entry_points
+                .iter()
+                .find(|(entry, _)| *entry == func)
+                .map(|(_, kind)| *kind),
+            status,
+            annotation: checked
+                .annotation
+                .as_ref()
+                .map(|annotation| annotation.text.trim().to_owned()),
+            findings,
+            justifications: checked
+                .justifications
+                .iter()
+                .map(|justification| Finding {
+                    message: justification.text.clone(),
+                    lines: vec![line_of(tcx, justification.span)],
+                })
+                .collect(),
+            external_calls,
+            snippet: Snippet::new(tcx, func),
+        }
+    }
+}
+
+impl Snippet {
+    /// The full source of a function (up to a limit).
+    fn new(tcx: TyCtxt, func: LocalDefId) -> Self {
+        let span = tcx
+            .hir_span_with_body(tcx.local_def_id_to_hir_id(func))
+            .source_callsite();
+        let sm = tcx.sess.source_map();
+        let start = sm.lookup_char_pos(span.lo());
+        let end = sm.lookup_char_pos(span.hi());
+        let lines = (start.line..=end.line)
+            .take(MAX_SNIPPET_LINES)
+            .map_while(|line| start.file.get_line(line - 1))
+            .map(Cow::into_owned)
+            .collect();
+
+        Snippet {
+            start_line: start.line,
+            lines,
+        }
+    }
+}
+
+/// Everything a function calls or drops, in a consistent order.
+fn callees(checked: &CheckedFn) -> Vec<(CallKind, &DefId, &Vec<Span>)> {
+    let calls = checked
+        .func
+        .calls_to
+        .iter()
+        .map(|(callee, spans)| (CallKind::Call, callee, spans));
+    let drops = checked
+        .func
+        .drops
+        .iter()
+        .map(|(callee, spans)| (CallKind::Drop, callee, spans));
+    let mut callees = calls.chain(drops).collect::<Vec<_>>();
+    callees.sort_by_key(|(kind, _, spans)| (spans.first().copied(), *kind == CallKind::Drop));
+    callees
+}
+
+/// The lines each issue in an error is on.
+fn error_lines<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> Vec<usize> {
+    let spans = match error {
+        LocalError::Basic {
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => unjustified_axioms
+            .iter()
+            .map(|axiom| axiom.span)
+            .chain(
+                unjustified_calls
+                    .iter()
+                    .flat_map(|calls| calls.from_spans.iter().copied()),
+            )
+            .collect(),
+        _ => vec![err::primary_span(tcx, error)],
+    };
+    spans.into_iter().map(|span| line_of(tcx, span)).collect()
+}
+
+fn line_of(tcx: TyCtxt, span: Span) -> usize {
+    tcx.sess.source_map().lookup_char_pos(span.lo()).line
+}
@@ -0,0 +1,386 @@
+//! A versioned JSON report of everything we found while checking a crate, for consumption by
+//! other tools (e.g. CI dashboards) that shouldn't have to scrape our diagnostics.
+//!
+//! The types here are the schema of that report, so any changes to them that could break
+//! consumers must also bump [`SCHEMA_VERSION`].
+
+use std::{collections::BTreeMap, path::Path};
+
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+use serde::Serialize;
+
+use crate::{
+    annotations::{AnnotationSource, DefAnnotation, PropertyViolation},
+    check::{CheckResults, CheckStats, CheckedFn, LocalError, err},
+    codes::ErrorCode,
+    properties::Property,
+    reachability::{CallKind, CallsWObligations, EntryKind},
+    report::{SourceLocation, write_report},
+};
+
+/// The version of the report's schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Write a JSON report of the `results` of checking the current crate into `dir`.
+pub fn write<P: Property>(
+    tcx: TyCtxt,
+    dir: &Path,
+    is_dependency: bool,
+    results: &CheckResults<'_, P>,
+) {
+    let report = CrateReport::new(tcx, is_dependency, results);
+    write_report(tcx, dir, "json", |out| {
+        serde_json::to_writer_pretty(&mut *out, &report)?;
+        writeln!(out)?;
+        Ok(())
+    });
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrateReport {
+    pub schema_version: u32,
+    #[serde(rename = "crate")]
+    pub krate: CrateInfo,
+    /// The property this crate was checked for (e.g. `unsafe`).
+    pub property: &'static str,
+    pub passed: bool,
+    pub entry_points: Vec<EntryPointReport>,
+    /// Every function reachable from the entry points.
+    pub functions: Vec<FunctionReport>,
+    /// The errors that fail the check.
+    pub errors: Vec<ErrorReport>,
+    /// Errors that are only warned about, because of their kind's severity.
+    pub warnings: Vec<ErrorReport>,
+    /// Errors suppressed by `allow` attributes, which don't fail the check.
+    pub suppressed: Vec<SuppressedReport>,
+    pub stats: StatsReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrateInfo {
+    pub name: String,
+    /// The name of this crate's reports, which is unique within a workspace.
+    pub report_name: String,
+    pub is_dependency: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryPointReport {
+    pub function: String,
+    pub kind: EntryKind,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionReport {
+    pub function: String,
+    pub location: SourceLocation,
+    /// The chain of calls from an entry point through which this function is reachable.
+    pub reachable_through: Vec<ReachableStep>,
+    pub annotation: Option<AnnotationReport>,
+    pub justifications: Vec<JustificationReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReachableStep {
+    pub function: String,
+    /// Where the next function in the chain is called from.
+    pub call_location: SourceLocation,
+    /// Whether the next function is called directly, or run by a value dropped there.
+    pub kind: CallKind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnotationReport {
+    pub text: String,
+    pub source: AnnotationSourceKind,
+    pub violation: ViolationKind,
+    /// The conditions under which the property is violated, if `violation` is `conditional`.
+    pub conditions: Vec<ConditionReport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationSourceKind {
+    DocComment,
+    TomlOverride,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    Unconditional,
+    Conditional,
+    Never,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConditionReport {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JustificationReport {
+    pub text: String,
+    /// The axiom or call being justified.
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    /// The stable code for this kind of error, see `cargo sniff-test --explain`.
+    pub code: ErrorCode,
+    pub function: String,
+    pub message: String,
+    pub location: SourceLocation,
+    #[serde(flatten)]
+    pub details: ErrorDetails,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuppressedReport {
+    pub reason: String,
+    /// Where the `allow` attribute is.
+    pub attribute: SourceLocation,
+    #[serde(flatten)]
+    pub error: ErrorReport,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ErrorDetails {
+    /// Axioms or calls to functions with obligations that weren't justified.
+    Unjustified {
+        axioms: Vec<AxiomReport>,
+        calls: Vec<CallReport>,
+    },
+    /// A trait method implementation whose annotation doesn't match the trait's definition.
+    InconsistentWithTrait {
+        #[serde(rename = "trait")]
+        trait_name: String,
+    },
+    /// A callsite justification that doesn't consider all of the callee's conditions.
+    CallMissedObligations {
+        justification: String,
+        missed_conditions: Vec<String>,
+    },
+    /// A function annotated with obligations that's missing a keyword (e.g. `unsafe`).
+    MissingKeyword { keyword: &'static str },
+}
+
+#[derive(Debug, Serialize)]
+pub struct AxiomReport {
+    pub description: String,
+    pub location: SourceLocation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CallReport {
+    pub call_to: String,
+    pub kind: CallKind,
+    pub locations: Vec<SourceLocation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsReport {
+    pub entry_points: BTreeMap<EntryKind, usize>,
+    pub functions_checked: usize,
+    pub with_obligations: usize,
+    pub without_obligations: usize,
+    pub calls_checked: usize,
+    /// The number of individual issues suppressed by `allow` attributes.
+    pub suppressed: usize,
+}
+
+impl CrateReport {
+    pub fn new<P: Property>(
+        tcx: TyCtxt,
+        is_dependency: bool,
+        results: &CheckResults<'_, P>,
+    ) -> Self {
+        CrateReport {
+            schema_version: SCHEMA_VERSION,
+            krate: CrateInfo {
+                name: tcx.crate_name(LOCAL_CRATE).to_string(),
+                report_name: super::report_file_stem(tcx),
+                is_dependency,
+            },
+            property: P::property_name(),
+            passed: results.passed(),
+            entry_points: results
+                .entry_points
+                .iter()
+                .map(|(def, kind)| EntryPointReport {
+                    function: tcx.def_path_str(def.to_def_id()),
+                    kind: *kind,
+                    location: SourceLocation::new(tcx, tcx.def_span(*def)),
+                })
+                .collect(),
+            functions: results
+                .functions
+                .iter()
+                .map(|checked| FunctionReport::new(tcx, checked))
+                .collect(),
+            errors: results
+                .errors
+                .iter()
+                .map(|error| ErrorReport::new(tcx, error))
+                .collect(),
+            warnings: results
+                .warnings
+                .iter()
+                .map(|error| ErrorReport::new(tcx, error))
+                .collect(),
+            suppressed: results
+                .suppressed
+                .iter()
+                .map(|suppressed| SuppressedReport {
+                    reason: suppressed.reason.clone(),
+                    attribute: SourceLocation::new(tcx, suppressed.attr_span),
+                    error: ErrorReport::new(tcx, &suppressed.error),
+                })
+                .collect(),
+            stats: StatsReport::from(&results.stats),
+        }
+    }
+}
+
+impl FunctionReport {
+    fn new(tcx: TyCtxt, checked: &CheckedFn) -> Self {
+        FunctionReport {
+            function: tcx.def_path_str(checked.func.reach.to_def_id()),
+            location: SourceLocation::new(tcx, tcx.def_span(checked.func.reach)),
+            reachable_through: checked
+                .func
+                .through
+                .iter()
+                .map(|(def, span, kind)| ReachableStep {
+                    function: tcx.def_path_str(def.to_def_id()),
+                    call_location: SourceLocation::new(tcx, *span),
+                    kind: *kind,
+                })
+                .collect(),
+            annotation: checked.annotation.as_ref().map(AnnotationReport::from),
+            justifications: checked
+                .justifications
+                .iter()
+                .map(|justification| JustificationReport {
+                    text: justification.text.clone(),
+                    location: SourceLocation::new(tcx, justification.span),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&DefAnnotation> for AnnotationReport {
+    fn from(annotation: &DefAnnotation) -> Self {
+        let (violation, conditions) = match &annotation.local_violation_annotation {
+            PropertyViolation::Unconditional => (ViolationKind::Unconditional, Vec::new()),
+            PropertyViolation::Conditionally(conditions) => (
+                ViolationKind::Conditional,
+                conditions
+                    .iter()
+                    .map(|condition| ConditionReport {
+                        name: condition.node.name.clone(),
+                        description: condition.node.description.trim().to_owned(),
+                    })
+                    .collect(),
+            ),
+            PropertyViolation::Never => (ViolationKind::Never, Vec::new()),
+        };
+
+        AnnotationReport {
+            text: annotation.text.trim().to_owned(),
+            source: match annotation.source {
+                AnnotationSource::DocComment(_) => AnnotationSourceKind::DocComment,
+                AnnotationSource::TomlOverride => AnnotationSourceKind::TomlOverride,
+            },
+            violation,
+            conditions,
+        }
+    }
+}
+
+impl ErrorReport {
+    fn new<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> Self {
+        let details = match error {
+            LocalError::Basic {
+                unjustified_axioms,
+                unjustified_calls,
+                ..
+            } => ErrorDetails::Unjustified {
+                axioms: unjustified_axioms
+                    .iter()
+                    .map(|axiom| AxiomReport {
+                        description: axiom.axiom.to_string(),
+                        location: SourceLocation::new(tcx, axiom.span),
+                    })
+                    .collect(),
+                calls: unjustified_calls
+                    .iter()
+                    .map(|calls| CallReport::new(tcx, calls))
+                    .collect(),
+            },
+            LocalError::Trait {
+                inconsistent_w_trait,
+                ..
+            } => ErrorDetails::InconsistentWithTrait {
+                trait_name: tcx.def_path_str(*inconsistent_w_trait),
+            },
+            LocalError::CallMissedObligations {
+                callsite_comment,
+                obligations,
+                ..
+            } => ErrorDetails::CallMissedObligations {
+                justification: callsite_comment.trim().to_owned(),
+                missed_conditions: obligations
+                    .iter()
+                    .map(|condition| condition.node.name.clone())
+                    .collect(),
+            },
+            LocalError::FnDefShouldHaveKeyword { needed_keyword, .. } => {
+                ErrorDetails::MissingKeyword {
+                    keyword: needed_keyword,
+                }
+            }
+        };
+
+        ErrorReport {
+            code: error.kind().code(),
+            function: tcx.def_path_str(error.func().reach.to_def_id()),
+            message: err::message(tcx, error),
+            location: SourceLocation::new(tcx, err::primary_span(tcx, error)),
+            details,
+        }
+    }
+}
+
+impl CallReport {
+    fn new(tcx: TyCtxt, calls: &CallsWObligations) -> Self {
+        CallReport {
+            call_to: tcx.def_path_str(calls.call_to),
+            kind: calls.kind,
+            locations: calls
+                .from_spans
+                .iter()
+                .map(|span| SourceLocation::new(tcx, *span))
+                .collect(),
+        }
+    }
+}
+
+impl From<&CheckStats> for StatsReport {
+    fn from(stats: &CheckStats) -> Self {
+        StatsReport {
+            entry_points: stats.entrypoints.clone(),
+            functions_checked: stats.total_fns_checked,
+            with_obligations: stats.w_obligation,
+            without_obligations: stats.w_no_obligation,
+            calls_checked: stats.calls_checked,
+            suppressed: stats.suppressed,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Reports of what we found while checking a crate, for other tools and for reviewers.
+
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::CrateType;
+use serde::Serialize;
+use std::{io::Write, path::Path};
+
+pub mod audit;
+pub mod callgraph;
+pub mod html;
+pub mod json;
+pub mod sarif;
+pub mod surface;
+
+/// The name to use for report files about the crate currently being compiled.
+///
+/// Each rustc invocation is for a different crate (or a different target of the same crate),
+/// so this is something like `my_crate-lib`, `my_crate-bin` or `my_crate-lib-test`.
+pub fn report_file_stem(tcx: TyCtxt) -> String {
+    let kind = if tcx.crate_types().contains(&CrateType::Executable) {
+        "bin"
+    } else {
+        "lib"
+    };
+    let test = if tcx.sess.is_test_crate() {
+        "-test"
+    } else {
+        ""
+    };
+    format!("{}-{kind}{test}", tcx.crate_name(LOCAL_CRATE))
+}
+
+/// Write a serialized report into the directory `dir` (or to stdout if `dir` is `-`),
+/// emitting an error if that fails.
+pub fn write_report(
+    tcx: TyCtxt,
+    dir: &Path,
+    extension: &str,
+    serialize: impl FnOnce(&mut dyn Write) -> anyhow::Result<()>,
+) {
+    let res = if dir == Path::new("-") {
+        serialize(&mut std::io::stdout().lock())
+    } else {
+        let path = dir.join(format!("{}.{extension}", report_file_stem(tcx)));
+        std::fs::create_dir_all(dir)
+            .and_then(|()| std::fs::File::create(&path))
+            .map_err(anyhow::Error::from)
+            .and_then(|mut file| serialize(&mut file))
+            .map_err(|e| e.context(format!("couldn't write report to {}", path.display())))
+    };
+
+    if let Err(e) = res {
+        tcx.dcx().err(format!("{e:#}"));
+    }
+}
+
+/// A location in the source code, with 1-based lines and columns.
+#[derive(Debug, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl SourceLocation {
+    pub fn new(tcx: TyCtxt, span: rustc_span::Span) -> Self {
+        let (file, start_line, start_column, end_line, end_column) =
+            tcx.sess.source_map().span_to_location_info(span);
+        SourceLocation {
+            file: file.map_or_else(String::new, |file| file.name.prefer_local().to_string()),
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+}
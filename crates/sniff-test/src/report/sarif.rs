@@ -0,0 +1,426 @@
+//! A [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log of the
+//! errors found while checking a crate, for code-scanning and review tools.
+//!
+//! Only the subset of SARIF we need is modelled here.
+
+use std::path::Path;
+
+use clap::ValueEnum;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{Span, source_map::Spanned};
+use serde::Serialize;
+
+use crate::{
+    annotations::Condition,
+    check::{CheckResults, FindingKind, LocalError, err},
+    properties::Property,
+    reachability::{CallKind, LocallyReachable},
+    report::{SourceLocation, write_report},
+};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/cognitive-engineering-lab/sniff-test";
+
+/// Write a SARIF log of the errors in `results` for the current crate into `dir`.
+pub fn write<P: Property>(tcx: TyCtxt, dir: &Path, results: &CheckResults<'_, P>) {
+    let log = SarifLog {
+        schema: SCHEMA,
+        version: "2.1.0",
+        runs: vec![Run {
+            tool: Tool {
+                driver: ToolComponent {
+                    name: "sniff-test",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: INFORMATION_URI,
+                    rules: FindingKind::value_variants()
+                        .iter()
+                        .map(|kind| rule_descriptor(*kind))
+                        .collect(),
+                },
+            },
+            results: results
+                .errors
+                .iter()
+                .flat_map(|error| error_results(tcx, error))
+                .chain(results.warnings.iter().flat_map(|error| {
+                    error_results(tcx, error)
+                        .into_iter()
+                        .map(|result| SarifResult {
+                            level: "warning",
+                            ..result
+                        })
+                }))
+                .chain(results.suppressed.iter().flat_map(|suppressed| {
+                    let suppression = Suppression {
+                        kind: "inSource",
+                        justification: suppressed.reason.clone(),
+                        location: Location::new(tcx, suppressed.attr_span, None),
+                    };
+                    error_results(tcx, &suppressed.error)
+                        .into_iter()
+                        .map(move |result| SarifResult {
+                            suppressions: vec![suppression.clone()],
+                            ..result
+                        })
+                }))
+                .collect(),
+        }],
+    };
+
+    write_report(tcx, dir, "sarif", |out| {
+        serde_json::to_writer_pretty(&mut *out, &log)?;
+        writeln!(out)?;
+        Ok(())
+    });
+}
+
+/// Each kind of issue we report is its own SARIF rule, identified by its error code.
+fn rule_descriptor(kind: FindingKind) -> ReportingDescriptor {
+    let description = match kind {
+        FindingKind::UnjustifiedAxiom => {
+            "An axiom of the property is used without a justification, in a function that isn't annotated with the property"
+        }
+        FindingKind::UnjustifiedCall => {
+            "A function with obligations is called without a justification, in a function that isn't annotated with the property"
+        }
+        FindingKind::UnjustifiedDrop => {
+            "A value is dropped without a justification and its drop has obligations, in a function that isn't annotated with the property"
+        }
+        FindingKind::MissedObligations => {
+            "A callsite justification doesn't consider all of the conditions of the function it calls"
+        }
+        FindingKind::MissingKeyword => {
+            "A function annotated with the property is missing the keyword the property requires"
+        }
+        FindingKind::TraitInconsistency => {
+            "A trait method implementation has obligations inconsistent with the trait's definition"
+        }
+    };
+    ReportingDescriptor {
+        id: kind.code().as_str(),
+        name: kind
+            .to_possible_value()
+            .expect("no kinds are skipped")
+            .get_name()
+            .to_owned(),
+        short_description: Message::new(description),
+        default_configuration: Configuration { level: "error" },
+    }
+}
+
+/// Turn an error into SARIF results, splitting it so each result points at a single issue.
+fn error_results<P: Property>(tcx: TyCtxt, error: &LocalError<'_, P>) -> Vec<SarifResult> {
+    let func = error.func();
+    let fn_name = tcx.def_path_str(func.reach.to_def_id());
+    let code_flow = reachability_flow(tcx, func);
+    let result = |kind: FindingKind, message, span, related_locations| SarifResult {
+        rule_id: kind.code().as_str(),
+        rule_index: kind as usize,
+        level: "error",
+        message: Message::new(message),
+        locations: vec![Location::new(tcx, span, None).in_function(&fn_name)],
+        code_flows: vec![code_flow.clone()],
+        related_locations,
+        suppressions: Vec::new(),
+    };
+
+    match error {
+        LocalError::Basic {
+            unjustified_axioms,
+            unjustified_calls,
+            ..
+        } => {
+            let not_annotated = format!(
+                "in {} {fn_name} is not justified, and {fn_name} is not annotated {}",
+                err::fn_descr(tcx, func),
+                P::property_name()
+            );
+            let axioms = unjustified_axioms.iter().map(|axiom| {
+                let message = format!("{} {not_annotated}", axiom.axiom);
+                result(
+                    FindingKind::UnjustifiedAxiom,
+                    message,
+                    axiom.span,
+                    Vec::new(),
+                )
+            });
+            let calls = unjustified_calls.iter().flat_map(|calls| {
+                let call_to = tcx.def_path_str(calls.call_to);
+                let message = match calls.kind {
+                    CallKind::Call => format!("call to {call_to} {not_annotated}"),
+                    CallKind::Drop => format!("drop running {call_to} {not_annotated}"),
+                };
+                let kind = FindingKind::for_call(calls.kind);
+                calls.from_spans.iter().map(move |span| {
+                    let conditions = condition_locations(tcx, &calls.conditions);
+                    result(kind, message.clone(), *span, conditions)
+                })
+            });
+            axioms.chain(calls).collect()
+        }
+        LocalError::CallMissedObligations { obligations, .. } => vec![result(
+            FindingKind::MissedObligations,
+            err::message(tcx, error),
+            err::primary_span(tcx, error),
+            condition_locations(tcx, obligations),
+        )],
+        LocalError::FnDefShouldHaveKeyword { .. } => vec![result(
+            FindingKind::MissingKeyword,
+            err::message(tcx, error),
+            err::primary_span(tcx, error),
+            Vec::new(),
+        )],
+        LocalError::Trait {
+            inconsistent_w_trait,
+            ..
+        } => {
+            let trait_def = Location::new(
+                tcx,
+                tcx.def_span(*inconsistent_w_trait),
+                Some("which is defined here".to_owned()),
+            )
+            .with_id(0);
+            vec![result(
+                FindingKind::TraitInconsistency,
+                err::message(tcx, error),
+                err::primary_span(tcx, error),
+                vec![trait_def],
+            )]
+        }
+    }
+}
+
+/// The chain of calls from an entry point to the function with an error.
+fn reachability_flow(tcx: TyCtxt, func: &LocallyReachable) -> CodeFlow {
+    let steps = func.through.iter().map(|(def, span, kind)| {
+        let name = tcx.def_path_str(def.to_def_id());
+        let message = match kind {
+            CallKind::Call => format!("{name} calls the next function here"),
+            CallKind::Drop => format!("{name} drops a value running the next function here"),
+        };
+        Location::new(tcx, *span, Some(message)).in_function(&name)
+    });
+    let fn_name = tcx.def_path_str(func.reach.to_def_id());
+    let end = Location::new(
+        tcx,
+        tcx.def_span(func.reach),
+        Some(format!("{fn_name} is defined here")),
+    )
+    .in_function(&fn_name);
+
+    CodeFlow {
+        thread_flows: vec![ThreadFlow {
+            locations: steps
+                .chain(std::iter::once(end))
+                .map(|location| ThreadFlowLocation { location })
+                .collect(),
+        }],
+    }
+}
+
+/// Related locations for the conditions of a callee, skipping any without a location
+/// in the source (e.g. from a TOML override).
+fn condition_locations(tcx: TyCtxt, conditions: &[Spanned<Condition>]) -> Vec<Location> {
+    conditions
+        .iter()
+        .filter(|condition| !condition.span.is_dummy())
+        .enumerate()
+        .map(|(id, condition)| {
+            let message = format!(
+                "condition `{}`: {}",
+                condition.node.name,
+                condition.node.description.trim()
+            );
+            Location::new(tcx, condition.span, Some(message)).with_id(id)
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: ToolComponent,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<ReportingDescriptor>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+    id: &'static str,
+    name: String,
+    short_description: Message,
+    default_configuration: Configuration,
+}
+
+#[derive(Debug, Serialize)]
+struct Configuration {
+    level: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    code_flows: Vec<CodeFlow>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressions: Vec<Suppression>,
+}
+
+/// Why a result doesn't count, which tools show as a dismissed alert.
+#[derive(Debug, Clone, Serialize)]
+struct Suppression {
+    kind: &'static str,
+    justification: String,
+    location: Location,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Message {
+    text: String,
+}
+
+impl Message {
+    fn new(text: impl Into<String>) -> Self {
+        Message { text: text.into() }
+    }
+}
+
+// The field names here are from the SARIF spec.
+#[allow(clippy::struct_field_names)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<LogicalLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+impl Location {
+    fn new(tcx: TyCtxt, span: Span, message: Option<String>) -> Self {
+        Location {
+            id: None,
+            physical_location: PhysicalLocation::from(SourceLocation::new(tcx, span)),
+            logical_locations: Vec::new(),
+            message: message.map(Message::new),
+        }
+    }
+
+    fn with_id(mut self, id: usize) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    fn in_function(mut self, fn_name: &str) -> Self {
+        self.logical_locations.push(LogicalLocation {
+            fully_qualified_name: fn_name.to_owned(),
+            kind: "function",
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+impl From<SourceLocation> for PhysicalLocation {
+    fn from(location: SourceLocation) -> Self {
+        // Relative paths are relative to where cargo invoked rustc, which is the workspace root.
+        let artifact_location = if Path::new(&location.file).is_absolute() {
+            ArtifactLocation {
+                uri: format!("file://{}", location.file),
+                uri_base_id: None,
+            }
+        } else {
+            ArtifactLocation {
+                uri: location.file,
+                uri_base_id: Some("%SRCROOT%"),
+            }
+        };
+
+        PhysicalLocation {
+            artifact_location,
+            region: Region {
+                start_line: location.start_line,
+                start_column: location.start_column,
+                end_line: location.end_line,
+                end_column: location.end_column,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogicalLocation {
+    fully_qualified_name: String,
+    kind: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeFlow {
+    thread_flows: Vec<ThreadFlow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ThreadFlow {
+    locations: Vec<ThreadFlowLocation>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ThreadFlowLocation {
+    location: Location,
+}
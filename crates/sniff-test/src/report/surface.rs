@@ -0,0 +1,212 @@
+//! A Markdown report of the unsafe APIs of other crates that a crate uses, grouped by the crate
+//! they come from, for supply-chain reviews of its exposure to third-party unsafe code.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{Span, Symbol};
+
+use crate::{
+    check::{CheckResults, CheckedFn},
+    properties::Property,
+    reachability,
+    report::{
+        SourceLocation,
+        audit::{has_obligations, justification_cell, relative_path},
+        write_report,
+    },
+};
+
+/// Write the external unsafe surface of the current crate into `dir`, as something like
+/// `my_crate-lib.unsafe-surface.md`.
+pub fn write<P: Property>(tcx: TyCtxt, dir: &Path, results: &CheckResults<'_, P>) {
+    // Links are relative to wherever the document ends up.
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let links_from = if dir == Path::new("-") {
+        cwd.clone()
+    } else {
+        dir.to_path_buf()
+    };
+    let surface = Surface {
+        tcx,
+        uses: external_unsafe_uses(tcx, &results.functions),
+        functions: results.functions.len(),
+        cwd,
+        links_from,
+    };
+
+    write_report(tcx, dir, "unsafe-surface.md", |out| surface.render(out));
+}
+
+/// A call from one of our reachable functions to an unsafe function in another crate.
+struct Use<'a> {
+    caller: &'a CheckedFn,
+    span: Span,
+}
+
+struct Surface<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// The uses of each external unsafe function, by the crate it's from.
+    uses: BTreeMap<Symbol, BTreeMap<String, Vec<Use<'a>>>>,
+    functions: usize,
+    /// Where rustc was run from, which source paths are relative to.
+    cwd: PathBuf,
+    links_from: PathBuf,
+}
+
+fn external_unsafe_uses<'a>(
+    tcx: TyCtxt,
+    functions: &'a [CheckedFn],
+) -> BTreeMap<Symbol, BTreeMap<String, Vec<Use<'a>>>> {
+    let mut uses: BTreeMap<_, BTreeMap<_, Vec<_>>> = BTreeMap::new();
+    for caller in functions {
+        let external =
+            caller.func.calls_to.iter().filter(|(callee, _)| {
+                !callee.is_local() && reachability::is_unsafe_fn(tcx, **callee)
+            });
+        for (callee, spans) in external {
+            // Calls expanded from another crate's macros are that crate's unsafe code, not ours.
+            let spans = spans
+                .iter()
+                .filter(|span| !span.in_external_macro(tcx.sess.source_map()))
+                .collect::<Vec<_>>();
+            if spans.is_empty() {
+                continue;
+            }
+            uses.entry(tcx.crate_name(callee.krate))
+                .or_default()
+                .entry(tcx.def_path_str(*callee))
+                .or_default()
+                .extend(spans.into_iter().map(|span| Use {
+                    caller,
+                    span: *span,
+                }));
+        }
+    }
+    // Order uses by where they are, so the report is deterministic.
+    for api_uses in uses.values_mut().flat_map(BTreeMap::values_mut) {
+        api_uses.sort_by_key(|api_use| {
+            let location = SourceLocation::new(tcx, api_use.span);
+            (location.file, location.start_line, location.start_column)
+        });
+    }
+    uses
+}
+
+impl Surface<'_, '_> {
+    fn render(&self, out: &mut dyn Write) -> anyhow::Result<()> {
+        let tcx = self.tcx;
+        let all_uses = self
+            .uses
+            .values()
+            .flat_map(BTreeMap::values)
+            .flatten()
+            .collect::<Vec<_>>();
+        let justified = all_uses.iter().filter(|api_use| is_justified(api_use));
+
+        writeln!(
+            out,
+            "# External unsafe surface of `{}`\n",
+            tcx.crate_name(LOCAL_CRATE)
+        )?;
+        writeln!(
+            out,
+            "Generated by sniff-test from the {} functions reachable from its entry points, which \
+             make {} calls to {} unsafe functions from {} other crates, {} of them justified.\n",
+            self.functions,
+            all_uses.len(),
+            self.uses.values().map(BTreeMap::len).sum::<usize>(),
+            self.uses.len(),
+            justified.count(),
+        )?;
+
+        for (krate, apis) in &self.uses {
+            writeln!(out, "## `{krate}`\n")?;
+            writeln!(out, "| Function | Uses | Justified | Called from |")?;
+            writeln!(out, "| --- | --- | --- | --- |")?;
+            for (api, api_uses) in apis {
+                let mut callers = Vec::new();
+                for api_use in api_uses {
+                    let caller = self.fn_name(api_use.caller.func.reach.to_def_id());
+                    if !callers.contains(&caller) {
+                        callers.push(caller);
+                    }
+                }
+                writeln!(
+                    out,
+                    "| `{api}` | {} | {} | {} |",
+                    api_uses.len(),
+                    api_uses
+                        .iter()
+                        .filter(|api_use| is_justified(api_use))
+                        .count(),
+                    callers.join(", "),
+                )?;
+            }
+            writeln!(out)?;
+
+            writeln!(out, "| Function | Caller | Location | Justification |")?;
+            writeln!(out, "| --- | --- | --- | --- |")?;
+            for (api, api_uses) in apis {
+                for api_use in api_uses {
+                    writeln!(
+                        out,
+                        "| `{api}` | {} | {} | {} |",
+                        self.fn_name(api_use.caller.func.reach.to_def_id()),
+                        self.link(api_use.span),
+                        self.justification(api_use),
+                    )?;
+                }
+            }
+            writeln!(out)?;
+        }
+
+        if self.uses.is_empty() {
+            writeln!(out, "No unsafe functions from other crates are called.")?;
+        }
+        Ok(())
+    }
+
+    fn justification(&self, api_use: &Use) -> String {
+        if has_obligations(api_use.caller) {
+            format!(
+                "*covered by {}'s own annotation*",
+                self.fn_name(api_use.caller.func.reach.to_def_id())
+            )
+        } else {
+            justification_cell(api_use.caller, api_use.span)
+        }
+    }
+
+    fn fn_name(&self, def_id: DefId) -> String {
+        format!("`{}`", self.tcx.def_path_str(def_id))
+    }
+
+    /// A Markdown link to the line of `span`.
+    fn link(&self, span: Span) -> String {
+        let location = SourceLocation::new(self.tcx, span);
+        let target = relative_path(&self.cwd.join(&location.file), &self.links_from);
+        format!(
+            "[{}:{}]({}#L{})",
+            location.file,
+            location.start_line,
+            target.display(),
+            location.start_line
+        )
+    }
+}
+
+/// Whether a use is justified, either at the call or by an annotation on its caller.
+fn is_justified(api_use: &Use) -> bool {
+    has_obligations(api_use.caller)
+        || api_use
+            .caller
+            .justifications
+            .iter()
+            .any(|justification| justification.span == api_use.span)
+}
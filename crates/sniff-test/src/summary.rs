@@ -0,0 +1,293 @@
+//! A summary of every crate analysed in a `cargo sniff-test` run.
+//!
+//! Each crate is analysed by a separate driver process, so the drivers each record their results
+//! in a file in the target directory, keyed by the hash cargo gives that unit. These are kept
+//! alongside cargo's own outputs, so crates cargo doesn't need to check again keep the results
+//! from when they were last analysed. Once cargo has finished, the front end collects the results
+//! of the units cargo checked in this run, either fresh from a driver or replayed from its cache.
+
+use std::{
+    collections::HashSet,
+    env,
+    ffi::OsString,
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use rustc_middle::ty::TyCtxt;
+use serde::{Deserialize, Serialize};
+
+use crate::{PrintAllItemsPlugin, check::CheckStats};
+
+/// The environment variable telling drivers where to record their results, which also marks the
+/// front end process that actually runs cargo.
+const DIR_ENV: &str = "SNIFF_TEST_SUMMARY_DIR";
+
+/// The results of analysing a single crate.
+#[derive(Debug, Serialize, Deserialize)]
+struct CrateSummary {
+    name: String,
+    is_dependency: bool,
+    entry_points: usize,
+    functions_checked: usize,
+    obligations: usize,
+    calls_checked: usize,
+    /// The number of individual issues that failed the check.
+    failures: usize,
+    /// The number of errors `--fix` edited the source for, which haven't been checked since.
+    fixed: usize,
+}
+
+/// Record the results of analysing the current crate for the front end, if it asked for them.
+pub fn record(tcx: TyCtxt, is_dependency: bool, stats: &CheckStats, failures: usize, fixed: usize) {
+    let Some(dir) = env::var_os(DIR_ENV) else {
+        return;
+    };
+    // Only cargo's own units have a hash; other invocations of the driver (like the probes some
+    // build scripts compile) aren't crates of the workspace or its dependencies.
+    let Some(unit) = tcx.sess.opts.cg.extra_filename.strip_prefix('-') else {
+        return;
+    };
+    let summary = CrateSummary {
+        name: crate::report::report_file_stem(tcx),
+        is_dependency,
+        entry_points: stats.entrypoints.values().sum(),
+        functions_checked: stats.total_fns_checked,
+        obligations: stats.w_obligation,
+        calls_checked: stats.calls_checked,
+        failures,
+        fixed,
+    };
+    let path = Path::new(&dir).join(format!("{unit}.json"));
+    let res = serde_json::to_string(&summary)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(fs::write(&path, json)?))
+        .with_context(|| format!("couldn't record a summary in {}", path.display()));
+    if let Err(e) = res {
+        tcx.dcx().warn(format!("{e:#}"));
+    }
+}
+
+/// Run `cargo sniff-test`, printing a summary of every crate analysed at the end.
+///
+/// `rustc_plugin` exits as soon as cargo does, so the actual run happens in a child process.
+pub fn cli_main() -> ExitCode {
+    if env::var_os(DIR_ENV).is_some() {
+        rustc_plugin::cli_main(PrintAllItemsPlugin);
+        return ExitCode::SUCCESS;
+    }
+
+    let dir = match summary_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            // Let `rustc_plugin` report whatever's wrong with the workspace as it usually would.
+            log::debug!("not summarising the run: {e:#}");
+            rustc_plugin::cli_main(PrintAllItemsPlugin);
+            return ExitCode::SUCCESS;
+        }
+    };
+
+    let started = SystemTime::now();
+    let mut child = env::current_exe()
+        .and_then(|exe| {
+            Command::new(exe)
+                .args(env::args_os().skip(1))
+                .env(DIR_ENV, &dir)
+                .stdout(Stdio::piped())
+                .spawn()
+        })
+        .expect("failed to run cargo-sniff-test");
+    let fresh_units = forward_output(child.stdout.take().expect("stdout is piped"));
+    let status = child.wait().expect("failed to wait for cargo-sniff-test");
+
+    let summaries = match read_summaries(&dir, started, &fresh_units) {
+        Ok(summaries) => summaries,
+        Err(e) => {
+            eprintln!("warning: couldn't read the summary of each crate: {e:#}");
+            Vec::new()
+        }
+    };
+    if !summaries.is_empty() {
+        print_table(&summaries);
+    }
+
+    if !status.success() {
+        let code = status.code().unwrap_or(1);
+        return ExitCode::from(u8::try_from(code).unwrap_or(1));
+    }
+    if summaries.iter().any(|summary| summary.failures > 0) {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Ask cargo for its JSON messages, so the front end can tell which units it checked, if the front
+/// end is collecting summaries.
+pub fn modify_cargo(cargo: &mut Command, cargo_args: &[String]) {
+    let custom_format = cargo_args
+        .iter()
+        .any(|arg| arg.starts_with("--message-format"));
+    if env::var_os(DIR_ENV).is_some() && !custom_format {
+        // Diagnostics are still rendered to stderr as usual.
+        cargo.arg("--message-format=json-render-diagnostics");
+    }
+}
+
+/// Pass on everything cargo and the drivers print except cargo's JSON messages, returning the
+/// hashes of the units cargo didn't need to check again.
+fn forward_output(stdout: impl std::io::Read) -> HashSet<String> {
+    let mut fresh_units = HashSet::new();
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        let message = serde_json::from_str::<serde_json::Value>(&line)
+            .ok()
+            .filter(|message| message["reason"].is_string());
+        let Some(message) = message else {
+            println!("{line}");
+            continue;
+        };
+        if message["reason"] == "compiler-artifact" && message["fresh"] == true {
+            let filenames = message["filenames"].as_array().into_iter().flatten();
+            fresh_units.extend(filenames.filter_map(|file| unit_hash(file.as_str()?)));
+        }
+    }
+    fresh_units
+}
+
+/// The hash of the unit that produced a file like `target/debug/deps/libfoo-0123456789abcdef.rmeta`.
+fn unit_hash(file: &str) -> Option<String> {
+    let stem = Path::new(file).file_stem()?.to_str()?;
+    let (_, hash) = stem.rsplit_once('-')?;
+    Some(hash.to_owned())
+}
+
+/// The directory for each crate's summary, next to the outputs of the `cargo check` that
+/// `rustc_plugin` runs.
+fn summary_dir() -> anyhow::Result<PathBuf> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let output = Command::new(cargo)
+        .args([
+            "metadata",
+            "--no-deps",
+            "--offline",
+            "--format-version",
+            "1",
+        ])
+        .output()?;
+    anyhow::ensure!(
+        output.status.success(),
+        "`cargo metadata` failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let target_dir = metadata["target_directory"]
+        .as_str()
+        .context("`cargo metadata` didn't give a target directory")?;
+
+    let dir = Path::new(target_dir)
+        .join(format!("plugin-{}", rustc_plugin::CHANNEL))
+        .join("sniff-test-summary");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Read the summaries of the units cargo checked in this run: those the drivers recorded since it
+/// `started`, and the `fresh_units` cargo had already checked. Others are left over from earlier runs
+/// (e.g. of crates that have since been removed, or with different cargo arguments).
+fn read_summaries(
+    dir: &Path,
+    started: SystemTime,
+    fresh_units: &HashSet<String>,
+) -> anyhow::Result<Vec<CrateSummary>> {
+    let mut summaries: Vec<CrateSummary> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let recorded_now = fs::metadata(&path)?.modified()? >= started;
+        let is_fresh = path
+            .file_stem()
+            .and_then(|unit| unit.to_str())
+            .is_some_and(|unit| fresh_units.contains(unit));
+        if !recorded_now && !is_fresh {
+            continue;
+        }
+        let json = fs::read_to_string(&path)?;
+        summaries.push(
+            serde_json::from_str(&json)
+                .with_context(|| format!("couldn't parse {}", path.display()))?,
+        );
+    }
+    summaries.sort_by(|a, b| (a.is_dependency, &a.name).cmp(&(b.is_dependency, &b.name)));
+    Ok(summaries)
+}
+
+fn print_table(summaries: &[CrateSummary]) {
+    const HEADERS: [&str; 5] = [
+        "entry points",
+        "functions checked",
+        "obligations",
+        "calls checked",
+        "failures",
+    ];
+
+    let name = |summary: &CrateSummary| {
+        if summary.is_dependency {
+            format!("{} (dep)", summary.name)
+        } else {
+            summary.name.clone()
+        }
+    };
+    let total = CrateSummary {
+        name: "total".to_owned(),
+        is_dependency: false,
+        entry_points: summaries.iter().map(|s| s.entry_points).sum(),
+        functions_checked: summaries.iter().map(|s| s.functions_checked).sum(),
+        obligations: summaries.iter().map(|s| s.obligations).sum(),
+        calls_checked: summaries.iter().map(|s| s.calls_checked).sum(),
+        failures: summaries.iter().map(|s| s.failures).sum(),
+        fixed: summaries.iter().map(|s| s.fixed).sum(),
+    };
+    let width = summaries
+        .iter()
+        .map(|summary| name(summary).len())
+        .chain(["crate".len(), total.name.len()])
+        .max()
+        .unwrap_or_default();
+
+    println!();
+    println!("{:<width$}  {}", "crate", HEADERS.join("  "));
+    for summary in summaries.iter().chain([&total]) {
+        println!(
+            "{:<width$}  {:>w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {:>w4$}",
+            name(summary),
+            summary.entry_points,
+            summary.functions_checked,
+            summary.obligations,
+            summary.calls_checked,
+            summary.failures,
+            w0 = HEADERS[0].len(),
+            w1 = HEADERS[1].len(),
+            w2 = HEADERS[2].len(),
+            w3 = HEADERS[3].len(),
+            w4 = HEADERS[4].len(),
+        );
+    }
+
+    let failed = summaries.iter().filter(|s| s.failures > 0).count();
+    if failed == 0 && total.fixed > 0 {
+        println!(
+            "no crate(s) FAILED the sniff test, but {} fixed error(s) will only be checked on the next run",
+            total.fixed
+        );
+    } else if failed == 0 {
+        println!("all {} crate(s) pass the sniff test", summaries.len());
+    } else {
+        println!(
+            "{failed} of {} crate(s) FAILED the sniff test",
+            summaries.len()
+        );
+    }
+}
@@ -48,9 +48,12 @@ impl TryFrom<Output> for SniffTestOutput {
     }
 }
 
-#[test]
-fn snapshots() -> anyhow::Result<()> {
-    // rebuild sniff-test to ensure we're using an updated version
+/// Where tests that edit a fixture work on a copy of it. This is as deep in the repo as the
+/// fixtures are, so their relative paths to our crates still work.
+const SCRATCH_DIR: &str = "../target/tests";
+
+// rebuild sniff-test to ensure we're using an updated version
+static REBUILT: LazyLock<()> = LazyLock::new(|| {
     let build_res = Command::new("cargo")
         .arg("build")
         .arg("--release")
@@ -61,6 +64,11 @@ fn snapshots() -> anyhow::Result<()> {
         build_res.status.success(),
         "rebuilding sniff-test didn't succeed"
     );
+});
+
+#[test]
+fn snapshots() -> anyhow::Result<()> {
+    LazyLock::force(&REBUILT);
 
     let root = Path::new(".").canonicalize()?;
 
@@ -234,14 +242,183 @@ fn cargo_sniff(path: &Path) -> anyhow::Result<SniffTestOutput> {
     .next()
     .expect("file shouldn't be empty")?;
 
+    // skip the first one, as it's the "#" or "//" to start a comment
+    cargo_sniff_with_args(path, first_line.split(' ').skip(1))
+}
+
+fn cargo_sniff_with_args<I, S>(path: &Path, args: I) -> anyhow::Result<SniffTestOutput>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
     let mut cmd = Command::new(&*CARGO_SNIFF_TEST_PATH);
-    cmd.args(first_line.split(' ').skip(1)); // skip the first one, as it's the "#" or "//" to start a comment
+    cmd.args(args);
     cmd.env("CARGO_TERM_COLOR", "never");
     cmd.current_dir(path);
 
     Ok(cmd.output()?.try_into()?)
 }
 
+/// Copy the fixture at `path` (without its build outputs) into a fresh scratch directory.
+fn scratch_copy(path: &Path) -> anyhow::Result<PathBuf> {
+    let name = path.file_name().expect("directory should have name");
+    let scratch = std::path::absolute(Path::new(SCRATCH_DIR).join(name))?;
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+
+    let files = WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target");
+    for entry in files {
+        let entry = entry?;
+        let to = scratch.join(entry.path().strip_prefix(path)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&to)?;
+        } else {
+            std::fs::copy(entry.path(), &to)?;
+        }
+    }
+    Ok(scratch)
+}
+
+#[derive(Debug, Serialize)]
+struct FixOutput {
+    #[serde(flatten)]
+    run: SniffTestOutput,
+    fixed_source: String,
+}
+
+/// `--fix` edits a copy of `unsafe/fix`, and a second run finds nothing left to fix.
+#[test]
+fn fix() -> anyhow::Result<()> {
+    LazyLock::force(&REBUILT);
+
+    let root = Path::new(".").canonicalize()?;
+    let fixture = root.join("unsafe/fix");
+    let scratch = scratch_copy(&fixture)?;
+    let args = ["-d", "trust", "--fix"];
+    let source = scratch.join("src/main.rs");
+
+    let run = cargo_sniff_with_args(&scratch, args)?;
+    let fixed_source = std::fs::read_to_string(&source)?;
+
+    let rerun = cargo_sniff_with_args(&scratch, args)?;
+    assert_eq!(
+        std::fs::read_to_string(&source)?,
+        fixed_source,
+        "a second run with --fix shouldn't make any edits"
+    );
+    assert!(
+        !rerun.stdout.contains("applied"),
+        "a second run with --fix shouldn't have anything to fix: {rerun:?}"
+    );
+    assert_eq!(rerun.exit_code, Some(0), "{rerun:?}");
+
+    let mut filters = snapshot_filters(&root);
+    let scratch_str = scratch.to_str().expect("should be valid unicode");
+    filters.push((scratch_str, "[SCRATCH_DIR]"));
+    insta::with_settings!({
+        snapshot_path => fixture,
+        filters => filters,
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    }, {
+        insta::assert_toml_snapshot!("fix_applied", &FixOutput { run, fixed_source });
+    });
+    Ok(())
+}
+
+/// Changing the arguments checks crates again, even though cargo has nothing else to rebuild.
+#[test]
+fn changed_args() -> anyhow::Result<()> {
+    LazyLock::force(&REBUILT);
+
+    let root = Path::new(".").canonicalize()?;
+    let scratch = scratch_copy(&root.join("unsafe/closures"))?;
+
+    let allowed = cargo_sniff_with_args(&scratch, ["-d", "trust", "--allow", "unjustified-axiom"])?;
+    assert_eq!(allowed.exit_code, Some(0), "{allowed:?}");
+
+    let denied = cargo_sniff_with_args(&scratch, ["-d", "trust"])?;
+    assert_eq!(denied.exit_code, Some(101), "{denied:?}");
+    assert!(
+        denied.stderr.contains("unjustified unsafe axiom"),
+        "the crate should be checked again: {denied:?}"
+    );
+
+    // Reports are written even though nothing has changed since the last run.
+    let reported = cargo_sniff_with_args(&scratch, ["-d", "trust", "--report-json", "-"])?;
+    assert!(
+        reported.stdout.contains("\"passed\": false"),
+        "the report should be written: {reported:?}"
+    );
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct MaxPathsOutput {
+    /// Explaining with `--max-paths` set to exactly the number of paths there are.
+    all: SniffTestOutput,
+    /// And with one fewer, so some are left out.
+    cut_short: SniffTestOutput,
+}
+
+#[test]
+fn explain_max_paths() -> anyhow::Result<()> {
+    LazyLock::force(&REBUILT);
+
+    let root = Path::new(".").canonicalize()?;
+    let fixture = root.join("unsafe/explain");
+    let scratch = scratch_copy(&fixture)?;
+    let explain = |max_paths| {
+        cargo_sniff_with_args(
+            &scratch,
+            [
+                "-d",
+                "trust",
+                "explain",
+                "inner::read",
+                "--max-paths",
+                max_paths,
+            ],
+        )
+    };
+
+    let out = MaxPathsOutput {
+        all: explain("5")?,
+        cut_short: explain("4")?,
+    };
+    assert!(
+        !out.all.stdout.contains("stopping there"),
+        "no paths were left out: {:?}",
+        out.all
+    );
+    assert!(
+        out.cut_short.stdout.contains("stopping there"),
+        "a path was left out: {:?}",
+        out.cut_short
+    );
+    let again = explain("4")?;
+    assert_eq!(
+        again.stdout, out.cut_short.stdout,
+        "explaining again should print the same paths"
+    );
+
+    let mut filters = snapshot_filters(&root);
+    let scratch_str = scratch.to_str().expect("should be valid unicode");
+    filters.push((scratch_str, "[SCRATCH_DIR]"));
+    insta::with_settings!({
+        snapshot_path => fixture,
+        filters => filters,
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    }, {
+        insta::assert_toml_snapshot!("explain_max_paths", &out);
+    });
+    Ok(())
+}
+
 fn rustc_sniff(file_path: &Path) -> anyhow::Result<SniffTestOutput> {
     let mut cmd = Command::new(&*SNIFF_TEST_DRIVER_PATH);
 
@@ -276,6 +453,112 @@ fn rustc_sniff(file_path: &Path) -> anyhow::Result<SniffTestOutput> {
     Ok(cmd.output()?.try_into()?)
 }
 
+#[derive(Debug, Serialize)]
+struct ChangedSinceOutput {
+    changed: SniffTestOutput,
+    invalid_revision: SniffTestOutput,
+}
+
+/// `--changed-since` on a copy of `unsafe/changed_since` committed to its own git repository,
+/// after editing one function, one callee's annotation and adding an untracked file.
+#[test]
+fn changed_since() -> anyhow::Result<()> {
+    LazyLock::force(&REBUILT);
+
+    let root = Path::new(".").canonicalize()?;
+    let fixture = root.join("unsafe/changed_since");
+    let scratch = scratch_copy(&fixture)?;
+    std::fs::write(scratch.join(".gitignore"), "target\n")?;
+    git(&scratch, &["init", "--quiet"])?;
+    git(&scratch, &["add", "."])?;
+    git(&scratch, &["commit", "--quiet", "--message", "before"])?;
+
+    let main = scratch.join("src/main.rs");
+    let source = std::fs::read_to_string(&main)?
+        .replace(
+            "fn edited(ptr: *const i32) -> i32 {\n    unsafe { *ptr }",
+            "fn edited(ptr: *const i32) -> i32 {\n    unsafe { *ptr + 1 }",
+        )
+        .replace(
+            "/// * aligned: ptr should be aligned\n",
+            "/// * aligned: ptr should be aligned for an i32\n",
+        )
+        .replace(
+            "fn main() {\n    let x = 1;\n",
+            "mod added;\n\nfn main() {\n    let x = 1;\n    added::added(&raw const x);\n",
+        );
+    std::fs::write(&main, source)?;
+    std::fs::write(
+        scratch.join("src/added.rs"),
+        "pub fn added(ptr: *const i32) -> i32 {\n    unsafe { *ptr }\n}\n",
+    )?;
+
+    let changed = cargo_sniff_with_args(&scratch, ["-d", "trust", "--changed-since", "HEAD"])?;
+    for reported in ["edited", "calls_read_aligned", "added::added"] {
+        assert!(
+            changed
+                .stderr
+                .contains(&format!("function {reported} directly")),
+            "{reported} changed, so should be reported: {changed:?}"
+        );
+    }
+    for ignored in ["untouched", "calls_read"] {
+        assert!(
+            !changed
+                .stderr
+                .contains(&format!("function {ignored} directly")),
+            "{ignored} didn't change, so shouldn't be reported: {changed:?}"
+        );
+    }
+
+    let invalid_revision = cargo_sniff_with_args(
+        &scratch,
+        ["-d", "trust", "--changed-since", "not-a-revision"],
+    )?;
+    assert_ne!(invalid_revision.exit_code, Some(0));
+    assert!(
+        !invalid_revision.stderr.contains("panicked"),
+        "an invalid revision should be a clean error: {invalid_revision:?}"
+    );
+
+    let mut filters = snapshot_filters(&root);
+    let scratch_str = scratch.to_str().expect("should be valid unicode");
+    filters.push((scratch_str, "[SCRATCH_DIR]"));
+    insta::with_settings!({
+        snapshot_path => fixture,
+        filters => filters,
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    }, {
+        insta::assert_toml_snapshot!(
+            "changed_since_git",
+            &ChangedSinceOutput { changed, invalid_revision }
+        );
+    });
+    Ok(())
+}
+
+fn git(dir: &Path, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "user.name=sniff-test",
+            "-c",
+            "user.email=sniff-test@example.com",
+        ])
+        .args(["-c", "commit.gpgsign=false"])
+        .args(args)
+        .current_dir(dir)
+        .output()?;
+    anyhow::ensure!(
+        output.status.success(),
+        "`git {}` failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
 const REVIEW_SCRIPT_PATH: &str = "../review.sh";
 
 fn write_review_script(cargo_dirs: &[PathBuf]) -> anyhow::Result<()> {
@@ -0,0 +1,38 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+/// * aligned: ptr should be aligned
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+/// # Safety
+/// The caller must make sure `ptr` is readable,
+/// at least for now.
+unsafe fn read_again(ptr: *const i32) -> i32 {
+    unsafe { read(ptr) }
+}
+
+fn checked_read(ptr: *const i32) -> Option<i32> {
+    if ptr.is_null() || !ptr.is_aligned() {
+        return None;
+    }
+    /// SAFETY:
+    /// - nn: we checked it's not null
+    /// - aligned: we checked it's aligned | so it's fine
+    let value = unsafe { read(ptr) };
+    Some(value)
+}
+
+fn main() {
+    let x = 1;
+    let ptr = &raw const x;
+    checked_read(ptr);
+    /// SAFETY: it points to a local
+    let y = unsafe { *ptr };
+    /// SAFETY: it points to a local, so it's readable
+    unsafe { read_again(ptr) };
+    unsafe { read(ptr) };
+    println!("{y}");
+}
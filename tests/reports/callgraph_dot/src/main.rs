@@ -0,0 +1,33 @@
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        println!("dropped");
+    }
+}
+
+fn helper(ptr: *const i32) -> i32 {
+    let _guard = Guard;
+    /// SAFETY:
+    /// - nn: it's from a reference
+    unsafe { read(ptr) }
+}
+
+#[allow(dead_code)]
+fn never_called() {}
+
+#[sniff_test_attrs::check_unsafe]
+fn entry() -> i32 {
+    let x = 1;
+    helper(&raw const x)
+}
+
+fn main() {
+    println!("{}", entry());
+}
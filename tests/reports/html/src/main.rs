@@ -0,0 +1,28 @@
+/// # Safety
+/// * nn: ptr should be non null
+/// * aligned: ptr should be aligned
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn helper(ptr: *const i32) -> i32 {
+    /// SAFETY:
+    /// - nn: it's from a reference
+    /// - aligned: it's from a reference
+    let value = unsafe { read(ptr) };
+    value
+}
+
+fn unchecked(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn entry() -> i32 {
+    let x = 1;
+    helper(&raw const x) + unchecked(&raw const x)
+}
+
+fn main() {
+    println!("{}", entry());
+}
@@ -0,0 +1,22 @@
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn justified() -> i32 {
+    let x = 1;
+    /// SAFETY:
+    /// - nn: pointers from references are trivially non-null
+    unsafe { read(&raw const x) }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn unjustified(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn main() {
+    println!("{} {}", justified(), unjustified(&raw const *&1));
+}
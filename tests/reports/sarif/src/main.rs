@@ -0,0 +1,21 @@
+/// # Safety
+/// * nn: ptr should be non null
+/// * aligned: ptr should be aligned
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn helper(ptr: *const i32) -> i32 {
+    let raw = unsafe { *ptr };
+    raw + unsafe { read(ptr) }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn entry() -> i32 {
+    let x = 1;
+    helper(&raw const x)
+}
+
+fn main() {
+    println!("{}", entry());
+}
@@ -0,0 +1,32 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+#[sniff_test_attrs::allow(unjustified_axiom, reason = "only ever called with a valid pointer")]
+fn whole_fn(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn one_statement(ptr: *const i32) -> i32 {
+    #[sniff_tool::allow(unjustified_call, reason = "checked for null above")]
+    let a = unsafe { read(ptr) };
+    let b = unsafe { read(ptr) };
+    a + b
+}
+
+fn stale(x: &i32) -> i32 {
+    #[sniff_tool::allow(unjustified_call, reason = "no longer needed")]
+    let a = *x;
+    a
+}
+
+fn main() {
+    let x = 1;
+    whole_fn(&raw const x);
+    one_statement(&raw const x);
+    stale(&x);
+}
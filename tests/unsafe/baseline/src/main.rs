@@ -0,0 +1,30 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn legacy(ptr: *const i32) -> i32 {
+    let a = unsafe { read(ptr) };
+    let b = unsafe { read(ptr) };
+    let c = unsafe { read(ptr) };
+    a + b + c + unsafe { *ptr }
+}
+
+fn since_fixed(ptr: *const i32) -> i32 {
+    /// SAFETY: the caller always passes a valid pointer
+    unsafe { *ptr }
+}
+
+fn added(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn main() {
+    let x = 1;
+    legacy(&raw const x);
+    since_fixed(&raw const x);
+    added(&raw const x);
+}
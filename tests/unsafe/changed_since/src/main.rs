@@ -0,0 +1,39 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+/// # Safety
+/// * aligned: ptr should be aligned
+unsafe fn read_aligned(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+// With `--changed-since`, each of these is only reported if it (or its callee's annotation)
+// changed since that revision.
+fn edited(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn untouched(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn calls_read(ptr: *const i32) -> i32 {
+    unsafe { read(ptr) }
+}
+
+fn calls_read_aligned(ptr: *const i32) -> i32 {
+    unsafe { read_aligned(ptr) }
+}
+
+fn main() {
+    let x = 1;
+    edited(&raw const x);
+    untouched(&raw const x);
+    calls_read(&raw const x);
+    calls_read_aligned(&raw const x);
+}
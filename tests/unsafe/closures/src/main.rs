@@ -0,0 +1,43 @@
+async fn read_later(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn sum_all(ptrs: &[*const i32]) -> i32 {
+    ptrs.iter().map(|ptr| unsafe { **ptr }).sum()
+}
+
+fn max_all(ptrs: &[*const i32]) -> Option<i32> {
+    ptrs.iter()
+        .map(|ptr| {
+            let value = unsafe { **ptr };
+            value.abs()
+        })
+        .max()
+}
+
+/// # Safety
+/// * nn: ptr should be non null
+async unsafe fn read_annotated(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+/// # Safety
+/// * nn: every pointer should be non null
+unsafe fn read_all(ptrs: &[*const i32]) -> Vec<i32> {
+    ptrs.iter().map(|ptr| unsafe { **ptr }).collect()
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn main() {
+    let x = 1;
+    let _future = read_later(&raw const x);
+    sum_all(&[&raw const x]);
+    max_all(&[&raw const x]);
+
+    /// SAFETY:
+    /// * nn: a pointer from a reference is non null
+    let _future = unsafe { read_annotated(&raw const x) };
+    /// SAFETY:
+    /// * nn: pointers from references are non null
+    unsafe { read_all(&[&raw const x]) };
+}
@@ -0,0 +1,49 @@
+struct RawBuf(*mut i32);
+
+impl Drop for RawBuf {
+    fn drop(&mut self) {
+        unsafe { *self.0 = 0 };
+    }
+}
+
+struct Wrapper {
+    _buf: RawBuf,
+}
+
+/// Resets what it points to when it's dropped, which `sniff-test.toml` says has to still be valid.
+struct Guard(*mut i32);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        unsafe { *self.0 = 0 };
+    }
+}
+
+fn justified(x: &mut i32) {
+    /// SAFETY:
+    /// - valid: x outlives the guard
+    let _ = Guard(x);
+}
+
+fn unjustified(x: &mut i32) {
+    let _ = Guard(x);
+}
+
+fn scoped(x: &mut i32) {
+    /// SAFETY:
+    /// - valid: x outlives the guard
+    {
+        let _guard = Guard(x);
+    }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn main() {
+    let mut x = 1;
+    justified(&mut x);
+    unjustified(&mut x);
+    scoped(&mut x);
+    let _wrapper = Wrapper {
+        _buf: RawBuf(&raw mut x),
+    };
+}
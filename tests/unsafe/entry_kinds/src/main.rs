@@ -0,0 +1,31 @@
+#![sniff_tool::entry_points(exported, trait_impls)]
+
+use std::fmt;
+
+#[unsafe(no_mangle)]
+pub extern "C" fn exported_read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+pub struct RawDisplay(*const i32);
+
+impl fmt::Display for RawDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", unsafe { *self.0 })
+    }
+}
+
+fn unused(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn reads_raw() {
+        let x = 1;
+        assert_eq!(unsafe { *(&raw const x) }, 1);
+    }
+}
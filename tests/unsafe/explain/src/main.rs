@@ -0,0 +1,30 @@
+mod inner {
+    /// # Safety
+    /// * nn: ptr should be non null
+    pub unsafe fn read(ptr: *const i32) -> i32 {
+        unsafe { *ptr }
+    }
+
+    pub fn read_ref(x: &i32) -> i32 {
+        /// SAFETY: references are never null
+        unsafe { read(x) }
+    }
+
+    /// # Safety
+    /// * nn: ptr should be non null
+    pub unsafe fn read_raw(ptr: *const i32) -> i32 {
+        unsafe { read(ptr) }
+    }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn through_both(x: &i32) -> i32 {
+    inner::read_ref(x) + unsafe { inner::read_raw(x) }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn main() {
+    let x = 1;
+    through_both(&x);
+    inner::read_ref(&x);
+}
@@ -0,0 +1,3 @@
+#![sniff_tool::check_unsafe]
+
+fn main() {}
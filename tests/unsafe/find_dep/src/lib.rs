@@ -0,0 +1,12 @@
+//! A path dependency of `find_posture`, with an unjustified call in its public API.
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+pub unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+pub fn read_unchecked(ptr: *const i32) -> i32 {
+    unsafe { read(ptr) }
+}
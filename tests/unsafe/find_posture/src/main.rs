@@ -0,0 +1,5 @@
+#[sniff_test_attrs::check_unsafe]
+fn main() {
+    let x = 1;
+    println!("{}", find_dep::read_unchecked(&raw const x));
+}
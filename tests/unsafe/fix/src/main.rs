@@ -0,0 +1,30 @@
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+/// # Safety
+/// * nn: ptr should be non null
+#[allow(dead_code)]
+#[sniff_test_attrs::check_unsafe]
+fn read_twice(ptr: *const i32) -> i32 {
+    unsafe { *ptr + *ptr }
+}
+
+fn add_one(ptr: *const i32) -> i32 {
+    let value = unsafe { read(ptr) };
+    value + 1
+}
+
+#[allow(dead_code)]
+#[sniff_test_attrs::check_unsafe]
+unsafe fn double(ptr: *const i32) -> i32 {
+    unsafe { read(ptr) * 2 }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn main() {
+    let x = 1;
+    println!("{}", add_one(&raw const x));
+}
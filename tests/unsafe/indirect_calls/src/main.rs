@@ -0,0 +1,52 @@
+use std::sync::LazyLock;
+
+trait Shape {
+    fn area(&self) -> i32;
+}
+
+struct Square(*const i32);
+
+impl Shape for Square {
+    fn area(&self) -> i32 {
+        // Only reachable through the `dyn Shape` call in `main`.
+        unsafe { *self.0 * *self.0 }
+    }
+}
+
+fn double(x: *const i32) -> i32 {
+    // Only reachable through the `fn` pointer call in `main`.
+    unsafe { *x * 2 }
+}
+
+struct Handler {
+    on_read: Box<dyn Fn(*const i32) -> i32 + Send + Sync>,
+}
+
+// Built lazily, so the closure is only reachable through the `dyn Fn` call in `main`.
+static HANDLER: LazyLock<Handler> = LazyLock::new(|| Handler {
+    on_read: Box::new(|ptr| unsafe { *ptr + 1 }),
+});
+
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+#[sniff_test_attrs::check_unsafe]
+fn main() {
+    let x = 1;
+
+    let shapes: Vec<Box<dyn Shape>> = vec![Box::new(Square(&raw const x))];
+    for shape in &shapes {
+        shape.area();
+    }
+
+    let f: fn(*const i32) -> i32 = double;
+    f(&raw const x);
+
+    (HANDLER.on_read)(&raw const x);
+
+    let g: unsafe fn(*const i32) -> i32 = read;
+    unsafe { g(&raw const x) };
+}
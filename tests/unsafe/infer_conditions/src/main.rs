@@ -0,0 +1,35 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+/// * aligned: ptr should be aligned
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+/// # Safety
+/// The caller must promise this is fine.
+unsafe fn trust_me() {}
+
+fn read_both(a: *const i32, b: *const i32) -> i32 {
+    let a = unsafe { read(a) };
+    let b = unsafe { *b };
+    unsafe { trust_me() };
+    a + b
+}
+
+fn read_twice(ptr: *const i32) -> i32 {
+    let first = read_both(ptr, ptr);
+    first
+}
+
+fn justified(ptr: *const i32) -> i32 {
+    /// SAFETY: the pointer is fine, trust me
+    read_both(ptr, ptr)
+}
+
+fn main() {
+    let x = 1;
+    read_twice(&raw const x);
+    justified(&raw const x);
+}
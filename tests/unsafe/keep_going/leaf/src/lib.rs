@@ -0,0 +1,6 @@
+#![sniff_tool::check_unsafe]
+
+// Fails the sniff test, but with `--keep-going` the crate depending on it is still analysed.
+pub fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
@@ -0,0 +1,11 @@
+#![sniff_tool::check_unsafe]
+
+fn read_twice(ptr: *const i32) -> i32 {
+    unsafe { *ptr + *ptr }
+}
+
+fn main() {
+    let x = 1;
+    leaf::read(&raw const x);
+    read_twice(&raw const x);
+}
@@ -0,0 +1,41 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+#[allow(sniff_tool::unjustified_axiom)]
+fn allowed(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn expected(ptr: *const i32) -> i32 {
+    #[expect(sniff_tool::unjustified_call, reason = "checked for null above")]
+    let a = unsafe { read(ptr) };
+    a
+}
+
+#[expect(sniff_tool::unjustified_drop)]
+fn unfulfilled(x: &i32) -> i32 {
+    *x
+}
+
+#[warn(sniff_tool::unjustified_call)]
+fn warned(ptr: *const i32) -> i32 {
+    unsafe { read(ptr) }
+}
+
+fn denied(ptr: *const i32) -> i32 {
+    unsafe { read(ptr) }
+}
+
+fn main() {
+    let x = 1;
+    allowed(&raw const x);
+    expected(&raw const x);
+    unfulfilled(&x);
+    warned(&raw const x);
+    denied(&raw const x);
+}
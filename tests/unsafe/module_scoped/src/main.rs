@@ -0,0 +1,39 @@
+#[sniff_test_attrs::check_unsafe]
+mod checked {
+    pub fn read(ptr: *const i32) -> i32 {
+        unsafe { *ptr }
+    }
+
+    pub mod nested {
+        pub fn read_nested(ptr: *const i32) -> i32 {
+            unsafe { *ptr }
+        }
+    }
+}
+
+mod unchecked {
+    pub fn read_unchecked(ptr: *const i32) -> i32 {
+        unsafe { *ptr }
+    }
+}
+
+pub struct RawRef(*const i32);
+
+#[sniff_test_attrs::check_unsafe]
+impl RawRef {
+    pub fn get(&self) -> i32 {
+        unsafe { *self.0 }
+    }
+}
+
+fn main() {
+    let x = 1;
+    let raw = RawRef(&raw const x);
+    println!(
+        "{} {} {} {}",
+        checked::read(&raw const x),
+        checked::nested::read_nested(&raw const x),
+        unchecked::read_unchecked(&raw const x),
+        raw.get(),
+    );
+}
@@ -0,0 +1,24 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+/// * aligned: ptr should be aligned
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+fn mixed(ptr: *const i32) -> i32 {
+    let a = unsafe { read(ptr) };
+    a + unsafe { *ptr }
+}
+
+fn missed(ptr: *const i32) -> i32 {
+    /// SAFETY: ptr is nn
+    unsafe { read(ptr) }
+}
+
+fn main() {
+    let x = 1;
+    mixed(&raw const x);
+    missed(&raw const x);
+}
@@ -0,0 +1,25 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * nn: ptr should be non null
+/// * aligned: ptr should be aligned
+unsafe fn read(ptr: *const i32) -> i32 {
+    unsafe { *ptr }
+}
+
+/// Reads the same pointer twice.
+pub const fn read_twice(ptr: *const i32) -> (i32, i32) {
+    let pair = unsafe { (*ptr, *ptr) };
+    pair
+}
+
+fn read_and_add(ptr: *const i32) -> i32 {
+    let first = unsafe { read(ptr) };
+    first + unsafe { read(ptr) }
+}
+
+fn main() {
+    let x = 1;
+    read_twice(&raw const x);
+    read_and_add(&raw const x);
+}
@@ -0,0 +1,30 @@
+#![sniff_tool::check_unsafe]
+
+/// # Safety
+/// * valid: `bytes` must be valid UTF-8
+unsafe fn as_str(bytes: &[u8]) -> &str {
+    unsafe { std::str::from_utf8_unchecked(bytes) }
+}
+
+fn first(v: &[i32]) -> i32 {
+    /// SAFETY: `v` is never empty
+    unsafe { *v.get_unchecked(0) }
+}
+
+fn last(v: &[i32]) -> i32 {
+    unsafe { *v.get_unchecked(v.len() - 1) }
+}
+
+fn copy(src: &i32) -> i32 {
+    /// SAFETY: `src` is a reference, so it's valid for reads
+    unsafe { std::ptr::read(src) }
+}
+
+fn main() {
+    let v = vec![1, 2, 3];
+    first(&v);
+    last(&v);
+    copy(&v[0]);
+    /// SAFETY: the literal is ASCII
+    unsafe { as_str(b"hello") };
+}